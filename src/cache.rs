@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use unic_langid::LanguageIdentifier;
+
+/// A small least recently used cache of resolved `Accept-Language` header values.
+///
+/// Clones share the same entries, so that the cache is used by all clones of the service.
+#[derive(Debug, Clone)]
+pub(crate) struct HeaderCache(Arc<Mutex<Lru>>);
+
+#[derive(Debug)]
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (Option<LanguageIdentifier>, u64)>,
+}
+
+impl HeaderCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::with_capacity(capacity),
+        })))
+    }
+
+    /// Gets the resolved language identifier of a header value, or resolves it and caches
+    /// the result
+    pub(crate) fn get_or_insert_with(
+        &self,
+        header: &str,
+        resolve: impl FnOnce() -> Option<LanguageIdentifier>,
+    ) -> Option<LanguageIdentifier> {
+        if let Some(ident) = self.get(header) {
+            return ident;
+        }
+
+        let ident = resolve();
+        self.insert(header, ident.clone());
+
+        ident
+    }
+
+    fn get(&self, header: &str) -> Option<Option<LanguageIdentifier>> {
+        let mut lru = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        lru.tick += 1;
+        let tick = lru.tick;
+
+        lru.entries.get_mut(header).map(|(ident, last_used)| {
+            *last_used = tick;
+            ident.clone()
+        })
+    }
+
+    pub(crate) fn insert(&self, header: &str, ident: Option<LanguageIdentifier>) {
+        let mut lru = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        if lru.capacity == 0 {
+            return;
+        }
+
+        if lru.entries.len() >= lru.capacity && !lru.entries.contains_key(header) {
+            // Linear scan for the oldest entry, the cache is expected to be small
+            let oldest = lru
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                lru.entries.remove(&oldest);
+            }
+        }
+
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.entries.insert(header.to_string(), (ident, tick));
+    }
+
+    #[cfg(test)]
+    pub(crate) fn contains(&self, header: &str) -> bool {
+        let lru = self.0.lock().unwrap();
+
+        lru.entries.contains_key(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use unic_langid::langid;
+
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = HeaderCache::new(2);
+        cache.insert("en", Some(langid!("en")));
+        cache.insert("ja", Some(langid!("ja")));
+
+        // Use `en` so that `ja` is the oldest entry
+        assert_eq!(cache.get("en"), Some(Some(langid!("en"))));
+        cache.insert("fr", None);
+
+        assert!(cache.contains("en"));
+        assert!(!cache.contains("ja"));
+        assert_eq!(cache.get("fr"), Some(None));
+    }
+
+    #[test]
+    fn only_resolves_once() {
+        let cache = HeaderCache::new(4);
+        let mut calls = 0;
+
+        for _ in 0..3 {
+            let ident = cache.get_or_insert_with("en-US,en;q=0.5", || {
+                calls += 1;
+                Some(langid!("en-US"))
+            });
+            assert_eq!(ident, Some(langid!("en-US")));
+        }
+
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn zero_capacity_does_not_cache() {
+        let cache = HeaderCache::new(0);
+        cache.insert("en", Some(langid!("en")));
+
+        assert!(!cache.contains("en"));
+    }
+}
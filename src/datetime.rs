@@ -0,0 +1,179 @@
+use chrono::{DateTime, TimeZone};
+use unic_langid::LanguageIdentifier;
+
+/// The parts of a timestamp formatted by [crate::Localizer::format_datetime]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateTimeStyle {
+    /// The numeric date, Ex. `3/15/2024` in `en-US` or `2024/03/15` in `ja`
+    #[default]
+    Date,
+    /// The hours and minutes, Ex. `2:30 PM` in `en-US` or `14:30` in `ja`
+    Time,
+    /// The date followed by the time
+    DateTime,
+}
+
+impl DateTimeStyle {
+    /// Parses the name of a style, Ex. `date`, `time` or `datetime`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "date" => Some(Self::Date),
+            "time" => Some(Self::Time),
+            "datetime" => Some(Self::DateTime),
+            _ => None,
+        }
+    }
+}
+
+/// The date and time patterns of a locale, as `chrono` format strings
+struct Patterns {
+    date: &'static str,
+    time: &'static str,
+}
+
+const MONTH_DAY_YEAR_12H: Patterns = Patterns {
+    date: "%-m/%-d/%Y",
+    time: "%-I:%M %p",
+};
+const DAY_MONTH_YEAR_SLASH: Patterns = Patterns {
+    date: "%d/%m/%Y",
+    time: "%H:%M",
+};
+const DAY_MONTH_YEAR_DOT: Patterns = Patterns {
+    date: "%d.%m.%Y",
+    time: "%H:%M",
+};
+const DAY_MONTH_YEAR_DASH: Patterns = Patterns {
+    date: "%d-%m-%Y",
+    time: "%H:%M",
+};
+const YEAR_MONTH_DAY_SLASH: Patterns = Patterns {
+    date: "%Y/%m/%d",
+    time: "%H:%M",
+};
+const YEAR_MONTH_DAY_DASH: Patterns = Patterns {
+    date: "%Y-%m-%d",
+    time: "%H:%M",
+};
+const YEAR_MONTH_DAY_DASH_12H: Patterns = Patterns {
+    date: "%Y-%m-%d",
+    time: "%-I:%M %p",
+};
+const YEAR_MONTH_DAY_KO: Patterns = Patterns {
+    date: "%Y. %-m. %-d.",
+    time: "%H:%M",
+};
+
+/// Finds the patterns of a locale from its region, then its language, defaulting to ISO 8601
+fn patterns(locale: &LanguageIdentifier) -> &'static Patterns {
+    let region = locale.region.as_ref().map(|region| region.as_str());
+
+    match (locale.language.as_str(), region) {
+        ("en", Some("US" | "PH") | None) => &MONTH_DAY_YEAR_12H,
+        ("en", Some("CA")) => &YEAR_MONTH_DAY_DASH_12H,
+        ("en", Some(_)) => &DAY_MONTH_YEAR_SLASH,
+        ("fr", Some("CA")) => &YEAR_MONTH_DAY_DASH,
+        ("ja" | "zh", _) => &YEAR_MONTH_DAY_SLASH,
+        ("ko", _) => &YEAR_MONTH_DAY_KO,
+        ("de" | "ru" | "pl" | "tr" | "fi" | "nb" | "nn" | "no" | "cs" | "da" | "uk", _) => {
+            &DAY_MONTH_YEAR_DOT
+        }
+        ("nl", _) => &DAY_MONTH_YEAR_DASH,
+        ("fr" | "es" | "it" | "pt" | "el" | "vi" | "id", _) => &DAY_MONTH_YEAR_SLASH,
+        _ => &YEAR_MONTH_DAY_DASH,
+    }
+}
+
+/// Formats a timestamp with the numeric date and time conventions of a locale, in the time
+/// zone of the timestamp.
+///
+/// Locales without known conventions use ISO 8601, Ex. `2024-03-15 14:30`.
+pub(crate) fn format_datetime<Tz>(
+    locale: &LanguageIdentifier,
+    timestamp: &DateTime<Tz>,
+    style: DateTimeStyle,
+) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    let patterns = patterns(locale);
+
+    match style {
+        DateTimeStyle::Date => timestamp.format(patterns.date).to_string(),
+        DateTimeStyle::Time => timestamp.format(patterns.time).to_string(),
+        DateTimeStyle::DateTime => timestamp
+            .format(&format!("{} {}", patterns.date, patterns.time))
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, Utc};
+    use unic_langid::langid;
+
+    use super::*;
+
+    fn timestamp() -> DateTime<Utc> {
+        // 2024-03-05 14:30 UTC
+        DateTime::from_timestamp(1709649000, 0).unwrap()
+    }
+
+    #[test]
+    fn date_order_follows_locale() {
+        let ts = timestamp();
+
+        assert_eq!(
+            format_datetime(&langid!("en-US"), &ts, DateTimeStyle::Date),
+            "3/5/2024"
+        );
+        assert_eq!(
+            format_datetime(&langid!("en-GB"), &ts, DateTimeStyle::Date),
+            "05/03/2024"
+        );
+        assert_eq!(
+            format_datetime(&langid!("ja"), &ts, DateTimeStyle::Date),
+            "2024/03/05"
+        );
+        assert_eq!(
+            format_datetime(&langid!("de-DE"), &ts, DateTimeStyle::Date),
+            "05.03.2024"
+        );
+        assert_eq!(
+            format_datetime(&langid!("xx"), &ts, DateTimeStyle::Date),
+            "2024-03-05"
+        );
+    }
+
+    #[test]
+    fn time_follows_locale_and_time_zone() {
+        let ts = timestamp();
+
+        assert_eq!(
+            format_datetime(&langid!("en"), &ts, DateTimeStyle::Time),
+            "2:30 PM"
+        );
+        assert_eq!(
+            format_datetime(&langid!("ja"), &ts, DateTimeStyle::DateTime),
+            "2024/03/05 14:30"
+        );
+
+        let tokyo = ts.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+
+        assert_eq!(
+            format_datetime(&langid!("ja"), &tokyo, DateTimeStyle::DateTime),
+            "2024/03/05 23:30"
+        );
+    }
+
+    #[test]
+    fn can_parse_style_names() {
+        assert_eq!(DateTimeStyle::from_name("date"), Some(DateTimeStyle::Date));
+        assert_eq!(
+            DateTimeStyle::from_name("datetime"),
+            Some(DateTimeStyle::DateTime)
+        );
+        assert_eq!(DateTimeStyle::from_name("long"), None);
+    }
+}
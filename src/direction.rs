@@ -0,0 +1,78 @@
+use unic_langid::LanguageIdentifier;
+
+/// The direction of text in a locale, ex. for the `dir` attribute of HTML
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// The value of the HTML `dir` attribute, `ltr` or `rtl`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Scripts written from right to left
+const RTL_SCRIPTS: &[&str] = &[
+    "Adlm", "Arab", "Hebr", "Mand", "Nkoo", "Rohg", "Samr", "Syrc", "Thaa", "Yezi",
+];
+
+/// Languages written from right to left in their most likely script
+const RTL_LANGUAGES: &[&str] = &[
+    "ar", "arc", "ckb", "dv", "fa", "he", "iw", "ji", "ks", "mzn", "ps", "sd", "syr", "ug", "ur",
+    "yi",
+];
+
+/// Finds the text direction of a locale from its script, or from its language if it has no
+/// script subtag.
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(text_direction(&langid!("ar")), Direction::Rtl);
+/// assert_eq!(text_direction(&langid!("pa-Arab")), Direction::Rtl);
+/// ```
+pub fn text_direction(locale: &LanguageIdentifier) -> Direction {
+    let is_rtl = match locale.script {
+        Some(script) => RTL_SCRIPTS.contains(&script.as_str()),
+        None => RTL_LANGUAGES.contains(&locale.language.as_str()),
+    };
+
+    if is_rtl {
+        Direction::Rtl
+    } else {
+        Direction::Ltr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use unic_langid::langid;
+
+    use super::*;
+
+    #[test]
+    fn finds_direction_of_language() {
+        assert_eq!(text_direction(&langid!("ar")), Direction::Rtl);
+        assert_eq!(text_direction(&langid!("he-IL")), Direction::Rtl);
+        assert_eq!(text_direction(&langid!("en")), Direction::Ltr);
+        assert_eq!(text_direction(&langid!("en-US")).to_string(), "ltr");
+    }
+
+    #[test]
+    fn script_takes_precedence() {
+        assert_eq!(text_direction(&langid!("pa-Arab")), Direction::Rtl);
+        assert_eq!(text_direction(&langid!("az-Latn")), Direction::Ltr);
+        assert_eq!(text_direction(&langid!("sd-Deva")), Direction::Ltr);
+    }
+}
@@ -0,0 +1,58 @@
+use icu_experimental::displaynames::{
+    DisplayNamesOptions, LanguageDisplayNames, LocaleDisplayNamesFormatter,
+};
+use unic_langid::LanguageIdentifier;
+
+/// Finds the name of a locale in another locale from the CLDR data, Ex. `Japanese` for `ja` in
+/// `en`, or `British English` for `en-GB` in `en`.
+///
+/// Returns None if there is no data for the display locale, or if the language is unknown in it.
+pub(crate) fn display_name(
+    target: &LanguageIdentifier,
+    in_locale: &LanguageIdentifier,
+) -> Option<String> {
+    let target = to_icu_locale(target)?;
+    let in_locale = to_icu_locale(in_locale)?.into();
+
+    LanguageDisplayNames::try_new(&in_locale, DisplayNamesOptions::default())
+        .ok()?
+        .of(target.id.language)?;
+    let formatter =
+        LocaleDisplayNamesFormatter::try_new(&in_locale, DisplayNamesOptions::default()).ok()?;
+
+    Some(formatter.of(&target).into_owned())
+}
+
+/// Converts between the identifier types by their tag, as both implement BCP 47
+fn to_icu_locale(locale: &LanguageIdentifier) -> Option<icu_locid::Locale> {
+    locale.to_string().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use unic_langid::langid;
+
+    use super::*;
+
+    #[test]
+    fn finds_names_in_other_locales() {
+        assert_eq!(
+            display_name(&langid!("ja"), &langid!("en")).as_deref(),
+            Some("Japanese")
+        );
+        assert_eq!(
+            display_name(&langid!("en"), &langid!("ja")).as_deref(),
+            Some("英語")
+        );
+        assert_eq!(
+            display_name(&langid!("zh-Hant-TW"), &langid!("en-US")).as_deref(),
+            Some("Traditional Chinese (Taiwan)")
+        );
+    }
+
+    #[test]
+    fn unknown_locales_have_no_name() {
+        assert_eq!(display_name(&langid!("xx"), &langid!("en")), None);
+        assert_eq!(display_name(&langid!("ja"), &langid!("xx")), None);
+    }
+}
@@ -1,10 +1,44 @@
 use std::convert::Infallible;
 
-pub struct LanguageIdentifierExtractorError {}
+/// The part of the request a language identifier was extracted from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionSource {
+    /// The value of a header, ex. `Accept-Language`
+    Header(String),
+    /// The language segment of the request path
+    PathSegment(String),
+}
+
+pub struct LanguageIdentifierExtractorError {
+    attempted: Option<ExtractionSource>,
+}
+
+impl LanguageIdentifierExtractorError {
+    pub fn new(attempted: ExtractionSource) -> Self {
+        Self {
+            attempted: Some(attempted),
+        }
+    }
+
+    /// The header value or path segment that failed, if known
+    pub fn attempted(&self) -> Option<&ExtractionSource> {
+        self.attempted.as_ref()
+    }
+}
 
 impl std::fmt::Display for LanguageIdentifierExtractorError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Failed to extract language identifier from request.")
+        match &self.attempted {
+            Some(ExtractionSource::Header(value)) => write!(
+                f,
+                "Failed to extract language identifier from header value '{value}'."
+            ),
+            Some(ExtractionSource::PathSegment(segment)) => write!(
+                f,
+                "Failed to extract language identifier from path segment '{segment}'."
+            ),
+            None => write!(f, "Failed to extract language identifier from request."),
+        }
     }
 }
 
@@ -18,6 +52,34 @@ impl std::error::Error for LanguageIdentifierExtractorError {}
 
 impl From<Infallible> for LanguageIdentifierExtractorError {
     fn from(value: Infallible) -> Self {
-        Self {}
+        match value {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_names_attempted_value() {
+        let err =
+            LanguageIdentifierExtractorError::new(ExtractionSource::Header("xx-YY".to_string()));
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to extract language identifier from header value 'xx-YY'."
+        );
+        assert_eq!(
+            err.attempted(),
+            Some(&ExtractionSource::Header("xx-YY".to_string()))
+        );
+
+        let err =
+            LanguageIdentifierExtractorError::new(ExtractionSource::PathSegment("zz".to_string()));
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to extract language identifier from path segment 'zz'."
+        );
     }
 }
@@ -0,0 +1,218 @@
+use http::request::Parts;
+use unic_langid::LanguageIdentifier;
+
+/// The part of the request identifying a session, used to select the same locale for all of
+/// its requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeedSource {
+    /// The value of a cookie, Ex. a session id
+    Cookie(String),
+    /// The value of a header, Ex. `X-Session-Id`
+    Header(String),
+}
+
+/// Serves an alternate locale to a fraction of sessions, Ex. to A/B test a translation.
+///
+/// The selection hashes the seed and the value of the seed source, so a session always gets
+/// the same locale. Requests without a value for the seed source are not selected.
+///
+/// # Example
+/// ```ignore
+/// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+///     ENGLISH,
+///     vec![ENGLISH, langid!("en-GB")],
+///     axum_l10n::RedirectMode::NoRedirect,
+/// ).with_experiment(
+///     axum_l10n::LocaleExperiment::new(
+///         langid!("en-GB"),
+///         0.1,
+///         axum_l10n::SeedSource::Cookie("session".to_string()),
+///     )
+///     .seed(2024),
+/// )
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocaleExperiment {
+    alternate: LanguageIdentifier,
+    probability: f64,
+    seed_source: SeedSource,
+    seed: u64,
+}
+
+impl LocaleExperiment {
+    /// Selects `alternate` for the given fraction of sessions, clamped between 0 and 1
+    pub fn new(alternate: LanguageIdentifier, probability: f64, seed_source: SeedSource) -> Self {
+        Self {
+            alternate,
+            probability: probability.clamp(0.0, 1.0),
+            seed_source,
+            seed: 0,
+        }
+    }
+
+    /// Sets the seed of the selection, to select another set of sessions for a new experiment
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn alternate(&self) -> &LanguageIdentifier {
+        &self.alternate
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// Returns the alternate locale if the session of the request is selected
+    pub fn resolve(&self, req: &Parts) -> Option<LanguageIdentifier> {
+        let session = self.session_value(req)?;
+
+        if self.is_selected(session) {
+            Some(self.alternate.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Checks a session value falls in the selected fraction
+    fn is_selected(&self, session: &str) -> bool {
+        let hash = mix(fnv1a(&self.seed.to_le_bytes(), session.as_bytes()));
+
+        (hash as f64 / u64::MAX as f64) < self.probability
+    }
+
+    fn session_value<'a>(&self, req: &'a Parts) -> Option<&'a str> {
+        match &self.seed_source {
+            SeedSource::Header(name) => req
+                .headers
+                .get(name.as_str())
+                .and_then(|val| val.to_str().ok()),
+            SeedSource::Cookie(name) => req
+                .headers
+                .get_all(http::header::COOKIE)
+                .iter()
+                .filter_map(|val| val.to_str().ok())
+                .flat_map(|cookies| cookies.split(';'))
+                .filter_map(|cookie| cookie.trim().split_once('='))
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value),
+        }
+        .filter(|value| !value.is_empty())
+    }
+}
+
+/// The 64 bit FNV-1a hash, which is stable across Rust versions unlike the std hasher
+fn fnv1a(seed: &[u8], value: &[u8]) -> u64 {
+    seed.iter()
+        .chain(value)
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// The finalizer of MurmurHash3, as the high bits of FNV-1a barely change when only the last
+/// bytes of the value differ, Ex. `user-1` and `user-2`
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Request;
+    use unic_langid::langid;
+
+    use super::*;
+
+    fn parts_with_cookie(cookie: &str) -> Parts {
+        Request::builder()
+            .header("Cookie", cookie)
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    fn experiment(probability: f64) -> LocaleExperiment {
+        LocaleExperiment::new(
+            langid!("en-GB"),
+            probability,
+            SeedSource::Cookie("session".to_string()),
+        )
+        .seed(42)
+    }
+
+    #[test]
+    fn selection_is_deterministic() {
+        let experiment = experiment(0.5);
+
+        let selected: Vec<bool> = (0..20)
+            .map(|i| {
+                experiment
+                    .resolve(&parts_with_cookie(&format!("session=user-{i}")))
+                    .is_some()
+            })
+            .collect();
+
+        assert_eq!(
+            selected,
+            [
+                true, false, false, true, false, false, false, false, false, true, true, true,
+                true, true, false, true, false, true, false, true
+            ]
+        );
+
+        for _ in 0..3 {
+            assert_eq!(
+                experiment.resolve(&parts_with_cookie("theme=dark; session=user-0")),
+                Some(langid!("en-GB"))
+            );
+        }
+    }
+
+    #[test]
+    fn selects_fraction_of_sessions() {
+        let experiment = experiment(0.1);
+
+        let selected = (0..10_000)
+            .filter(|i| {
+                experiment
+                    .resolve(&parts_with_cookie(&format!("session={i}")))
+                    .is_some()
+            })
+            .count();
+
+        assert!((800..1200).contains(&selected), "{selected}");
+
+        assert!((0..100).all(|i| experiment_never_or_always(0.0, i).is_none()));
+        assert!((0..100).all(|i| experiment_never_or_always(1.0, i).is_some()));
+    }
+
+    fn experiment_never_or_always(probability: f64, i: usize) -> Option<LanguageIdentifier> {
+        experiment(probability).resolve(&parts_with_cookie(&format!("session={i}")))
+    }
+
+    #[test]
+    fn skips_requests_without_session() {
+        let experiment = experiment(1.0);
+
+        assert_eq!(experiment.resolve(&parts_with_cookie("theme=dark")), None);
+
+        let header_experiment = LocaleExperiment::new(
+            langid!("en-GB"),
+            1.0,
+            SeedSource::Header("X-Session-Id".to_string()),
+        );
+        let parts = Request::builder()
+            .header("X-Session-Id", "abc")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        assert_eq!(header_experiment.resolve(&parts), Some(langid!("en-GB")));
+    }
+}
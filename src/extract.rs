@@ -0,0 +1,389 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use http::{request::Parts, StatusCode, Uri};
+use unic_langid::LanguageIdentifier;
+
+/// Extracts the language identifier resolved by [crate::LanguageIdentifierExtractorLayer].
+///
+/// # Example
+/// ```ignore
+/// async fn handler(Locale(lang): Locale) -> String {
+///     format!("Your language is: {}", lang)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub LanguageIdentifier);
+
+/// The language identifier resolved by [crate::LanguageIdentifierExtractorLayer], as inserted
+/// into the request extensions.
+///
+/// The layer also inserts the bare `LanguageIdentifier`, which another layer could overwrite.
+/// This wrapper is only inserted by this crate, so prefer it, or [Locale], in complex
+/// middleware stacks.
+///
+/// # Example
+/// ```ignore
+/// async fn handler(Extension(ResolvedLocale(lang)): Extension<ResolvedLocale>) -> String {
+///     format!("Your language is: {}", lang)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedLocale(pub LanguageIdentifier);
+
+/// The request URI before its language segment was stripped, Ex. `/ja/products/42`, to build
+/// canonical or alternate links.
+///
+/// Inserted into the request extensions in the redirect modes, when the request path has a
+/// language segment.
+///
+/// # Example
+/// ```ignore
+/// async fn handler(Extension(LocalizedUri(uri)): Extension<LocalizedUri>) -> String {
+///     format!("Canonical path: {}", uri.path())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedUri(pub Uri);
+
+/// Rejection used when no language identifier is found in the request extensions,
+/// which happens when the handler is not wrapped by [crate::LanguageIdentifierExtractorLayer].
+///
+/// Responds with a 500 Internal Server Error by default. The status can be changed by taking
+/// the extractor as a `Result`:
+///
+/// # Example
+/// ```ignore
+/// async fn handler(locale: Result<Locale, LocaleRejection>) -> Result<String, LocaleRejection> {
+///     let Locale(lang) = locale.map_err(|rejection| rejection.with_status(StatusCode::BAD_REQUEST))?;
+///
+///     Ok(format!("Your language is: {}", lang))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleRejection {
+    status: StatusCode,
+}
+
+impl LocaleRejection {
+    /// The machine-readable code of the rejection, also sent in the response body
+    pub const CODE: &'static str = "missing-locale";
+
+    pub fn new() -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Sets the status of the rejection response
+    pub fn with_status(self, status: StatusCode) -> Self {
+        Self { status }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn code(&self) -> &'static str {
+        Self::CODE
+    }
+}
+
+impl Default for LocaleRejection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for LocaleRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Missing language identifier in request extensions. Is LanguageIdentifierExtractorLayer applied?"
+        )
+    }
+}
+
+impl std::error::Error for LocaleRejection {}
+
+/// Responds with the status of the rejection and a plain text body of the form
+/// `missing-locale: <message>`
+impl IntoResponse for LocaleRejection {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            [(http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            format!("{}: {}", self.code(), self),
+        )
+            .into_response()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Locale
+where
+    S: Send + Sync,
+{
+    type Rejection = LocaleRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ResolvedLocale>()
+            .map(|ResolvedLocale(lang)| Locale(lang.clone()))
+            .ok_or_else(LocaleRejection::new)
+    }
+}
+
+/// Extracts where the language identifier was found, as [crate::LocaleSource] is inserted into
+/// the request extensions with it.
+#[async_trait]
+impl<S> FromRequestParts<S> for crate::LocaleSource
+where
+    S: Send + Sync,
+{
+    type Rejection = LocaleRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<crate::LocaleSource>()
+            .copied()
+            .ok_or_else(LocaleRejection::new)
+    }
+}
+
+/// Extracts the shared [crate::Localizer] from the router state together with the resolved
+/// language identifier.
+///
+/// The router state must provide an `Arc<Localizer>` through [axum::extract::FromRef], either
+/// by being an `Arc<Localizer>` itself or by deriving `FromRef` on a state struct.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Clone, FromRef)]
+/// struct AppState {
+///     localizer: Arc<Localizer>,
+/// }
+///
+/// async fn handler(translator: Translator) -> String {
+///     translator.t("test-key-a", None).unwrap_or_default()
+/// }
+/// ```
+#[cfg(feature = "fluent")]
+#[derive(Debug, Clone)]
+pub struct Translator {
+    localizer: std::sync::Arc<crate::Localizer>,
+    locale: LanguageIdentifier,
+}
+
+#[cfg(feature = "fluent")]
+impl Translator {
+    pub fn locale(&self) -> &LanguageIdentifier {
+        &self.locale
+    }
+
+    pub fn localizer(&self) -> &crate::Localizer {
+        &self.localizer
+    }
+
+    /// Format a FTL message into the resolved locale if available.
+    pub fn t(
+        &self,
+        key: &(impl crate::MessageKey + ?Sized),
+        args: Option<&fluent::FluentArgs>,
+    ) -> Option<String> {
+        self.localizer.format_message(&self.locale, key, args)
+    }
+}
+
+#[cfg(feature = "fluent")]
+#[async_trait]
+impl<S> FromRequestParts<S> for Translator
+where
+    S: Send + Sync,
+    std::sync::Arc<crate::Localizer>: axum::extract::FromRef<S>,
+{
+    type Rejection = LocaleRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Locale(locale) = Locale::from_request_parts(parts, state).await?;
+
+        Ok(Self {
+            localizer: axum::extract::FromRef::from_ref(state),
+            locale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, routing::get, Router};
+    use http::Request;
+    use tower::ServiceExt;
+    use unic_langid::langid;
+
+    use super::*;
+    use crate::{LanguageIdentifierExtractorLayer, RedirectMode};
+
+    const ENGLISH: LanguageIdentifier = langid!("en");
+    const JAPANESE: LanguageIdentifier = langid!("ja");
+
+    async fn handler(Locale(lang): Locale) -> String {
+        lang.to_string()
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn can_extract_locale() {
+        let router =
+            Router::new()
+                .route("/", get(handler))
+                .layer(LanguageIdentifierExtractorLayer::new(
+                    ENGLISH,
+                    vec![ENGLISH, JAPANESE],
+                    RedirectMode::NoRedirect,
+                ));
+
+        let req = Request::builder()
+            .uri("/")
+            .header("Accept-Language", "ja")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router.oneshot(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "ja");
+    }
+
+    #[tokio::test]
+    async fn can_extract_locale_source() {
+        let layer = LanguageIdentifierExtractorLayer::new(
+            ENGLISH,
+            vec![ENGLISH, JAPANESE],
+            RedirectMode::NoRedirect,
+        )
+        .locale_from_path()
+        .locale_query_param("lang")
+        .locale_cookie("lang")
+        .with_resolver(|req| {
+            req.headers
+                .get("X-User-Language")
+                .and_then(|val| val.to_str().ok())
+                .and_then(|val| val.parse().ok())
+        });
+        let router = Router::new()
+            .route(
+                "/*path",
+                get(
+                    |Locale(lang): Locale, source: crate::LocaleSource| async move {
+                        format!("{lang} {source:?}")
+                    },
+                ),
+            )
+            .layer(layer);
+
+        for (uri, header, expected) in [
+            ("/ja/lists", None, "ja Uri"),
+            ("/lists?lang=ja", None, "ja Query"),
+            ("/lists", Some(("Cookie", "lang=ja")), "ja Cookie"),
+            ("/lists", Some(("X-User-Language", "ja")), "ja Resolver"),
+            ("/lists", Some(("Accept-Language", "ja")), "ja Header"),
+            ("/lists", None, "en Default"),
+        ] {
+            let mut req = Request::builder().uri(uri);
+            if let Some((name, value)) = header {
+                req = req.header(name, value);
+            }
+
+            let response = router
+                .clone()
+                .oneshot(req.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(body_string(response).await, expected, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_without_layer() {
+        let router = Router::new().route("/", get(handler));
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+
+        let response = router.oneshot(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            body_string(response).await,
+            "missing-locale: Missing language identifier in request extensions. Is LanguageIdentifierExtractorLayer applied?"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejection_status_is_configurable() {
+        let router = Router::new().route(
+            "/",
+            get(|locale: Result<Locale, LocaleRejection>| async move {
+                locale
+                    .map(|Locale(lang)| lang.to_string())
+                    .map_err(|rejection| rejection.with_status(StatusCode::BAD_REQUEST))
+            }),
+        );
+
+        let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+
+        let response = router.oneshot(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body_string(response).await.starts_with("missing-locale: "));
+    }
+
+    #[cfg(feature = "fluent")]
+    #[tokio::test]
+    async fn can_extract_translator() {
+        use std::sync::Arc;
+
+        use crate::Localizer;
+
+        let mut localizer = Localizer::new();
+        localizer
+            .add_bundle(JAPANESE, &["test_data/main.ftl"])
+            .unwrap();
+
+        let router = Router::new()
+            .route(
+                "/",
+                get(|translator: Translator| async move {
+                    translator.t("test-key-a", None).unwrap_or_default()
+                }),
+            )
+            .layer(LanguageIdentifierExtractorLayer::new(
+                ENGLISH,
+                vec![ENGLISH, JAPANESE],
+                RedirectMode::NoRedirect,
+            ))
+            .with_state(Arc::new(localizer));
+
+        let req = Request::builder()
+            .uri("/")
+            .header("Accept-Language", "ja")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router.oneshot(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "Hello World");
+    }
+}
@@ -1,15 +1,103 @@
-use std::{collections::HashMap, error::Error, fmt::Debug, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use fluent::{bundle::FluentBundle, types::FluentNumberOptions, FluentArgs, FluentResource};
-use unic_langid::LanguageIdentifier;
+use fluent::{
+    bundle::FluentBundle,
+    types::{FluentNumber, FluentNumberOptions},
+    FluentArgs, FluentError, FluentResource, FluentValue,
+};
+use fluent_syntax::{ast, parser::ParserError};
+use intl_pluralrules::{operands::PluralOperands, PluralCategory, PluralRuleType, PluralRules};
+use unic_langid::{subtags::Language, LanguageIdentifier};
 
 pub type Bundle = FluentBundle<FluentResource, intl_memoizer::concurrent::IntlLangMemoizer>;
 
 pub type Locales = HashMap<LanguageIdentifier, Bundle>;
 
+type FluentFunction =
+    dyn for<'a> Fn(&[FluentValue<'a>], &FluentArgs) -> FluentValue<'a> + Sync + Send;
+
 pub struct Localizer {
     locales: Locales,
+    message_keys: HashMap<LanguageIdentifier, BTreeSet<String>>,
+    /// The FTL files each bundle was loaded from, to reload them
+    bundle_paths: HashMap<LanguageIdentifier, Vec<PathBuf>>,
+    /// The fallback locale for each language, to match regional locales without scanning
+    language_fallbacks: HashMap<Language, LanguageIdentifier>,
+    fallback_chain: HashMap<LanguageIdentifier, Vec<LanguageIdentifier>>,
+    /// The bundle used for every locale without a match, with the locale it was built for
+    fallback_bundle: Option<(LanguageIdentifier, LoadedBundle)>,
+    use_isolating: bool,
+    functions: Vec<(String, Arc<FluentFunction>)>,
+    default_locale: Option<LanguageIdentifier>,
+    macrolanguage_fallback: bool,
+    #[cfg(feature = "negotiation")]
+    negotiation: Option<fluent_langneg::NegotiationStrategy>,
     number_options: FluentNumberOptions,
+    list_separator: String,
+    #[cfg(feature = "tera")]
+    tera_config: crate::LocalizerTeraConfig,
+}
+
+/// Languages used interchangeably, as a macrolanguage and its individual languages, or as a
+/// language and its former code
+const EQUIVALENT_LANGUAGES: &[(&str, &str)] = &[("nb", "no"), ("nn", "no"), ("fil", "tl")];
+
+/// The languages equivalent to a language, in the order of [EQUIVALENT_LANGUAGES]
+fn equivalent_languages(language: Language) -> impl Iterator<Item = Language> {
+    EQUIVALENT_LANGUAGES.iter().filter_map(move |(a, b)| {
+        let equivalent = match language.as_str() {
+            lang if lang == *a => b,
+            lang if lang == *b => a,
+            _ => return None,
+        };
+
+        equivalent.parse().ok()
+    })
+}
+
+/// The identifiers of the messages and terms defined in a resource, terms keeping their `-` prefix
+fn resource_message_keys(resource: &FluentResource) -> impl Iterator<Item = String> + '_ {
+    resource.entries().filter_map(|entry| match entry {
+        ast::Entry::Message(message) => Some(message.id.name.to_string()),
+        ast::Entry::Term(term) => Some(format!("-{}", term.id.name)),
+        _ => None,
+    })
+}
+
+/// Prints, or emits as warnings with the `tracing` feature, the errors of a formatted message
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn report_errors(locale: &LanguageIdentifier, errors: Vec<FluentError>) {
+    for err in errors {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(locale = %locale, error = %err, "fluent formatting error");
+        #[cfg(not(feature = "tracing"))]
+        println!("{err}");
+    }
+}
+
+fn add_shared_function(
+    bundle: &mut Bundle,
+    name: &str,
+    func: &Arc<FluentFunction>,
+) -> Result<(), FluentError> {
+    let func = func.clone();
+
+    bundle.add_function(name, move |positional, named| func(positional, named))
+}
+
+/// A parsed bundle that has not been registered to a [Localizer] yet
+pub(crate) struct LoadedBundle {
+    bundle: Bundle,
+    message_keys: BTreeSet<String>,
+    /// The FTL files of the bundle, if it was only loaded from files
+    paths: Option<Vec<PathBuf>>,
 }
 
 impl std::fmt::Debug for Localizer {
@@ -27,23 +115,184 @@ impl std::fmt::Debug for Localizer {
 }
 
 #[derive(Debug)]
-pub struct LocalizerError {
-    cause: String,
+pub enum LocalizerError {
+    /// Failed to read an FTL file or directory
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// Failed to parse an FTL file, with the FTL source to locate the errors in
+    Parse {
+        path: PathBuf,
+        errors: Vec<ParserError>,
+        ftl: String,
+    },
+    /// Failed to parse an FTL source string, given by its index in the sources
+    ParseSource {
+        index: usize,
+        errors: Vec<ParserError>,
+        ftl: String,
+    },
+    /// Failed to load the bundle of a locale
+    Bundle {
+        locale: LanguageIdentifier,
+        source: Box<LocalizerError>,
+    },
+    /// No bundle matched the locale
+    LocaleNotFound(LanguageIdentifier),
+    /// The bundle did not contain the message, or the message did not contain the attribute
+    MessageNotFound {
+        key: String,
+        attribute: Option<String>,
+    },
+    /// The message only has attributes and no standalone value
+    MissingValue { key: String },
+    /// A value could not be used as a fluent argument
+    InvalidArgument { key: String, reason: String },
+    /// Failed to watch an FTL file for changes
+    #[cfg(feature = "watch")]
+    Watch {
+        path: Option<PathBuf>,
+        source: notify::Error,
+    },
+    /// Failed to open or read a zip archive
+    #[cfg(feature = "zip")]
+    Zip {
+        path: PathBuf,
+        source: zip::result::ZipError,
+    },
 }
 
-impl LocalizerError {
-    fn new(cause: String) -> Self {
-        Self { cause }
+impl std::fmt::Display for LocalizerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, .. } => {
+                write!(f, "Localizer error: failed to read from path: {:?}", path)
+            }
+            Self::Parse { path, errors, ftl } => {
+                write!(f, "Localizer error: failed to parse FTL: {:?}", path)?;
+                write_parse_errors(f, ftl, errors)
+            }
+            Self::ParseSource { index, errors, ftl } => {
+                write!(
+                    f,
+                    "Localizer error: failed to parse FTL source at index {}",
+                    index
+                )?;
+                write_parse_errors(f, ftl, errors)
+            }
+            Self::Bundle { locale, source } => {
+                write!(f, "failed to load bundle for locale {locale}: {source}")
+            }
+            Self::LocaleNotFound(locale) => write!(f, "could not find locale {locale}"),
+            Self::MessageNotFound {
+                key,
+                attribute: None,
+            } => write!(f, "could not find message with key={key}"),
+            Self::MessageNotFound {
+                key,
+                attribute: Some(attribute),
+            } => write!(
+                f,
+                "could not find attribute={attribute} for message with key={key}"
+            ),
+            Self::MissingValue { key } => write!(
+                f,
+                "message with key={key} does not have a standalone message"
+            ),
+            Self::InvalidArgument { key, reason } => {
+                write!(f, "invalid argument with key={key}: {reason}")
+            }
+            #[cfg(feature = "watch")]
+            Self::Watch {
+                path: Some(path),
+                source,
+            } => write!(
+                f,
+                "Localizer error: failed to watch path: {:?}, {:?}",
+                path, source
+            ),
+            #[cfg(feature = "watch")]
+            Self::Watch { path: None, source } => {
+                write!(
+                    f,
+                    "Localizer error: failed to start FTL watcher: {:?}",
+                    source
+                )
+            }
+            #[cfg(feature = "zip")]
+            Self::Zip { path, source } => write!(
+                f,
+                "Localizer error: failed to read zip archive: {:?}, {}",
+                path, source
+            ),
+        }
     }
 }
 
-impl std::fmt::Display for LocalizerError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Localizer error: {}", self.cause)
+fn to_path_bufs<P>(paths: &[P]) -> Vec<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    paths.iter().map(|path| path.as_ref().to_owned()).collect()
+}
+
+/// Matches the name of a zip entry against a glob pattern, using only the file name for
+/// patterns without a `/`
+#[cfg(feature = "zip")]
+fn zip_entry_matches(pattern: &str, name: &str) -> bool {
+    if pattern.contains('/') {
+        crate::glob::glob_matches(
+            &format!("/{}", pattern.trim_start_matches('/')),
+            &format!("/{name}"),
+        )
+    } else {
+        crate::glob::glob_matches(pattern, name)
+    }
+}
+
+/// Writes each parse error with its line and column, and the line of FTL it was found on
+fn write_parse_errors(
+    f: &mut std::fmt::Formatter<'_>,
+    ftl: &str,
+    errors: &[ParserError],
+) -> std::fmt::Result {
+    for err in errors {
+        let before = ftl.get(..err.pos.start).unwrap_or(ftl);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        let snippet = ftl[line_start..].lines().next().unwrap_or_default();
+
+        write!(
+            f,
+            "\n  line {line}, column {column}: {}\n  | {snippet}",
+            err.kind
+        )?;
+    }
+
+    Ok(())
+}
+
+impl std::error::Error for LocalizerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Bundle { source, .. } => Some(source.as_ref()),
+            #[cfg(feature = "watch")]
+            Self::Watch { source, .. } => Some(source),
+            #[cfg(feature = "zip")]
+            Self::Zip { source, .. } => Some(source),
+            _ => None,
+        }
     }
 }
 
-impl std::error::Error for LocalizerError {}
+impl Default for Localizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Localizer {
     pub fn new() -> Self {
@@ -51,11 +300,29 @@ impl Localizer {
 
         Self {
             locales,
+            message_keys: HashMap::new(),
+            bundle_paths: HashMap::new(),
+            language_fallbacks: HashMap::new(),
+            fallback_chain: HashMap::new(),
+            fallback_bundle: None,
+            use_isolating: true,
+            functions: Vec::new(),
+            default_locale: None,
+            macrolanguage_fallback: false,
+            #[cfg(feature = "negotiation")]
+            negotiation: None,
             number_options: FluentNumberOptions::default(),
+            list_separator: String::from(", "),
+            #[cfg(feature = "tera")]
+            tera_config: crate::LocalizerTeraConfig::default(),
         }
     }
 
     /// Set fluent number conversion options
+    ///
+    /// The options are applied to the numbers converted from JSON, Ex. in tera, and by
+    /// [Localizer::format_message_with_numbers]. Numbers passed in `FluentArgs` to
+    /// [Localizer::format_message] keep their own options.
     pub fn set_fluent_number_options(mut self, number_options: FluentNumberOptions) -> Self {
         self.number_options = number_options;
 
@@ -66,6 +333,167 @@ impl Localizer {
         &self.number_options
     }
 
+    /// Set the separator used to join list arguments into a single fluent value.
+    /// Defaults to `", "`.
+    pub fn set_list_separator(mut self, list_separator: &str) -> Self {
+        self.list_separator = list_separator.to_string();
+
+        self
+    }
+
+    pub fn list_separator(&self) -> &str {
+        &self.list_separator
+    }
+
+    /// Match locales in [Localizer::get_locale] with the `fluent-langneg` algorithm instead of
+    /// comparing languages, expanding likely subtags so that ex. `zh-TW` matches `zh-Hant`.
+    #[cfg(feature = "negotiation")]
+    pub fn set_negotiation_strategy(
+        mut self,
+        strategy: fluent_langneg::NegotiationStrategy,
+    ) -> Self {
+        self.negotiation = Some(strategy);
+
+        self
+    }
+
+    /// Set the locales to format messages from, in order, when a message is not found in the
+    /// requested locale.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Missing French messages are rendered in English
+    /// let localizer = Localizer::new()
+    ///     .set_fallback_chain(HashMap::from([(langid!("fr"), vec![langid!("en")])]));
+    /// ```
+    pub fn set_fallback_chain(
+        mut self,
+        fallback_chain: HashMap<LanguageIdentifier, Vec<LanguageIdentifier>>,
+    ) -> Self {
+        self.fallback_chain = fallback_chain;
+
+        self
+    }
+
+    /// Set the locale used by [Localizer::format_default]
+    pub fn set_default_locale(mut self, locale: LanguageIdentifier) -> Self {
+        self.default_locale = Some(locale);
+
+        self
+    }
+
+    pub fn default_locale(&self) -> Option<&LanguageIdentifier> {
+        self.default_locale.as_ref()
+    }
+
+    /// Set whether [Localizer::get_locale] falls back to equivalent languages when no bundle
+    /// matches the language, so that `nb` and `nn` match `no` and `fil` matches `tl`, and
+    /// the other way around. Defaults to `false`.
+    pub fn set_macrolanguage_fallback(mut self, macrolanguage_fallback: bool) -> Self {
+        self.macrolanguage_fallback = macrolanguage_fallback;
+
+        self
+    }
+
+    pub fn macrolanguage_fallback(&self) -> bool {
+        self.macrolanguage_fallback
+    }
+
+    /// Set whether interpolated values are wrapped in Unicode isolation marks (U+2068 and
+    /// U+2069), applying to loaded bundles and bundles added later. Defaults to `true`.
+    ///
+    /// Disable it for plain text such as emails, where the marks are unwanted.
+    pub fn set_use_isolating(mut self, use_isolating: bool) -> Self {
+        self.use_isolating = use_isolating;
+        for bundle in self.locales.values_mut() {
+            bundle.set_use_isolating(use_isolating);
+        }
+        if let Some((_, fallback)) = &mut self.fallback_bundle {
+            fallback.bundle.set_use_isolating(use_isolating);
+        }
+
+        self
+    }
+
+    pub fn use_isolating(&self) -> bool {
+        self.use_isolating
+    }
+
+    /// Registers a custom Fluent function on every loaded bundle, and on bundles added later.
+    ///
+    /// Returns an error if a bundle already has a function with the same name, in which case
+    /// the function is not registered on the bundles added later.
+    ///
+    /// # Example
+    /// ```ignore
+    /// localizer.add_function("SHOUT", |positional, _named| match positional {
+    ///     [FluentValue::String(s)] => FluentValue::from(s.to_uppercase()),
+    ///     _ => FluentValue::Error,
+    /// })?;
+    /// ```
+    pub fn add_function<F>(&mut self, name: &str, func: F) -> Result<(), FluentError>
+    where
+        F: for<'a> Fn(&[FluentValue<'a>], &FluentArgs) -> FluentValue<'a> + Sync + Send + 'static,
+    {
+        let func: Arc<FluentFunction> = Arc::new(func);
+
+        for bundle in self.locales.values_mut() {
+            add_shared_function(bundle, name, &func)?;
+        }
+        if let Some((_, fallback)) = &mut self.fallback_bundle {
+            add_shared_function(&mut fallback.bundle, name, &func)?;
+        }
+        self.functions.push((name.to_string(), func));
+
+        Ok(())
+    }
+
+    /// Set the parameter names read when used as a tera function or filter
+    #[cfg(feature = "tera")]
+    pub fn set_tera_config(mut self, tera_config: crate::LocalizerTeraConfig) -> Self {
+        self.tera_config = tera_config;
+
+        self
+    }
+
+    #[cfg(feature = "tera")]
+    pub fn tera_config(&self) -> &crate::LocalizerTeraConfig {
+        &self.tera_config
+    }
+
+    /// Creates a localizer with a bundle for each locale, loading the FTL files given by their
+    /// file paths as with [Localizer::add_bundle].
+    ///
+    /// Locales are loaded in sorted order, returning the first error with the locale that
+    /// failed to load.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let localizer = Localizer::from_map(HashMap::from([
+    ///     (ENGLISH, vec![PathBuf::from("locales/en/main.ftl")]),
+    ///     (JAPANESE, vec![PathBuf::from("locales/ja/main.ftl")]),
+    /// ]))?;
+    /// ```
+    pub fn from_map(
+        map: HashMap<LanguageIdentifier, Vec<PathBuf>>,
+    ) -> Result<Self, LocalizerError> {
+        let mut localizer = Self::new();
+
+        let mut entries = map.into_iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(locale, _)| locale.to_string());
+
+        for (locale, ftl_paths) in entries {
+            localizer
+                .add_bundle(locale.clone(), &ftl_paths)
+                .map_err(|err| LocalizerError::Bundle {
+                    locale,
+                    source: Box::new(err),
+                })?;
+        }
+
+        Ok(localizer)
+    }
+
     /// Adds a bundle to the localizer including all the FTL files given by their file paths
     ///
     /// If subsequent files contain the same keys as previous ones, those messages will be
@@ -80,197 +508,1430 @@ impl Localizer {
     where
         P: Debug + AsRef<Path>,
     {
-        let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
-
-        for path in ftl_paths {
-            let ftl = std::fs::read_to_string(path).map_err(|_err| {
-                LocalizerError::new(format!("failed to read from path: {:?}", path))
-            })?;
-            let ftl = FluentResource::try_new(ftl).map_err(|err| {
-                LocalizerError::new(format!(
-                    "failed to parse FTL: {:?}, with reason: {:?}",
-                    path, err.1
-                ))
-            })?;
-
-            bundle.add_resource_overriding(ftl);
-        }
+        let bundle = self.load_bundle(&locale, ftl_paths)?;
 
-        self.locales.insert(locale, bundle);
+        self.insert_bundle(locale, bundle);
 
         Ok(())
     }
 
-    /// Searches for a full locale match and returns it.
-    /// If no full locale match, returns a language match if available
-    pub fn get_locale(&self, locale: &LanguageIdentifier) -> Option<&Bundle> {
-        let full_locale_match = self.locales.get(locale);
+    /// Adds a bundle used for every locale that no registered bundle matches, Ex. for a small
+    /// site with a single FTL file for all its locales.
+    ///
+    /// The fallback bundle is tried last, after the fallback chain, by [Localizer::get_locale]
+    /// and the `format_message` methods. It is built for the default locale if set, see
+    /// [Localizer::set_default_locale], and is not listed by [Localizer::available_locales].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut localizer = Localizer::new().set_default_locale(ENGLISH);
+    /// localizer.add_fallback_bundle(&["locales/main.ftl"])?;
+    ///
+    /// let message = localizer.format_message(&langid!("fr"), "test-key-a", None);
+    /// ```
+    pub fn add_fallback_bundle<P>(&mut self, ftl_paths: &[P]) -> Result<(), LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let locale = self.default_locale.clone().unwrap_or_default();
+        let bundle = self.load_bundle(&locale, ftl_paths)?;
 
-        // Try to match only on the language if full match not found
-        match full_locale_match {
-            Some(l) => Some(l),
-            None => self
-                .locales
-                .keys()
-                .find(|k| k.language == locale.language)
-                .and_then(|key| self.locales.get(key)),
-        }
+        self.fallback_bundle = Some((locale, bundle));
+
+        Ok(())
     }
 
-    /// Format a FTL message into target locale if available.<br>
-    /// See Fluent RS [FluentBundle::format_pattern documentation](https://docs.rs/fluent/latest/fluent/bundle/struct.FluentBundle.html#method.format_pattern)
-    /// for details
-    ///
-    /// Fluent template errors are printed to stdout.
-    pub fn format_message(
+    /// Reads and parses the FTL files into a new bundle without registering it
+    pub(crate) fn load_bundle<P>(
         &self,
         locale: &LanguageIdentifier,
-        key: &(impl MessageKey + ?Sized),
-        args: Option<&FluentArgs>,
-    ) -> Option<String> {
-        self.format_message_result(locale, key, args).ok()
+        ftl_paths: &[P],
+    ) -> Result<LoadedBundle, LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let resources = Self::read_resources(ftl_paths)?;
+
+        let mut bundle = self.build_bundle(locale, resources);
+        bundle.paths = Some(to_path_bufs(ftl_paths));
+
+        Ok(bundle)
     }
 
-    /// Format a FTL message into target locale if available.<br>
-    /// See Fluent RS [FluentBundle::format_pattern documentation](https://docs.rs/fluent/latest/fluent/bundle/struct.FluentBundle.html#method.format_pattern)
-    /// for details
+    /// Re-reads and re-parses the FTL files of every bundle loaded from files, Ex. after a
+    /// deploy.
     ///
-    /// Fluent template errors are printed to stdout.
-    pub fn format_message_result(
-        &self,
-        locale: &LanguageIdentifier,
-        key: &(impl MessageKey + ?Sized),
-        args: Option<&FluentArgs>,
-    ) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
-        let bundle = self
-            .get_locale(locale)
-            .ok_or_else(|| format!("could not find locale {locale}"))?;
+    /// All the bundles are replaced at once, or none are if any file fails to load.
+    /// Bundles added with [Localizer::add_bundle_from_dir] reload the files found when they
+    /// were added, while bundles added from sources, zip archives or with
+    /// [Localizer::add_bundle_lenient] are kept as they are.
+    ///
+    /// # Example
+    /// ```ignore
+    /// async fn reload_translations(State(state): State<AppState>) -> StatusCode {
+    ///     match state.localizer.write().unwrap().reload() {
+    ///         Ok(()) => StatusCode::NO_CONTENT,
+    ///         Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    ///     }
+    /// }
+    /// ```
+    pub fn reload(&mut self) -> Result<(), LocalizerError> {
+        let mut bundles = Vec::with_capacity(self.bundle_paths.len());
 
-        let message = bundle
-            .get_message(key.key())
-            .ok_or_else(|| format!("could not find message with key={}", key.key()))?;
+        for (locale, paths) in &self.bundle_paths {
+            let bundle = self
+                .load_bundle(locale, paths)
+                .map_err(|err| LocalizerError::Bundle {
+                    locale: locale.clone(),
+                    source: Box::new(err),
+                })?;
 
-        let mut errors = Vec::new();
+            bundles.push((locale.clone(), bundle));
+        }
 
-        let message = if let Some(attribute) = key.attribute() {
-            let attribute = message.get_attribute(attribute).ok_or_else(|| {
-                format!(
-                    "could not find attribute={attribute} for message with key={}",
-                    key.key()
-                )
-            })?;
+        let fallback_bundle = match &self.fallback_bundle {
+            Some((
+                locale,
+                LoadedBundle {
+                    paths: Some(paths), ..
+                },
+            )) => {
+                let bundle =
+                    self.load_bundle(locale, paths)
+                        .map_err(|err| LocalizerError::Bundle {
+                            locale: locale.clone(),
+                            source: Box::new(err),
+                        })?;
 
-            bundle
-                .format_pattern(attribute.value(), args, &mut errors)
-                .to_string()
-        } else {
-            bundle
-                .format_pattern(
-                    message.value().ok_or_else(|| {
-                        format!(
-                            "message with key={} does not have a standalone message",
-                            key.key()
-                        )
-                    })?,
-                    args,
-                    &mut errors,
-                )
-                .to_string()
+                Some((locale.clone(), bundle))
+            }
+            _ => None,
         };
 
-        for err in errors {
-            println!("{err}");
+        for (locale, bundle) in bundles {
+            self.insert_bundle(locale, bundle);
+        }
+        if fallback_bundle.is_some() {
+            self.fallback_bundle = fallback_bundle;
         }
 
-        Ok(message)
+        Ok(())
     }
 
-    pub fn iter(&self) -> std::collections::hash_map::Iter<LanguageIdentifier, Bundle> {
-        self.locales.iter()
-    }
+    /// Reads and parses FTL files into resources
+    fn read_resources<P>(ftl_paths: &[P]) -> Result<Vec<FluentResource>, LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let mut resources = Vec::with_capacity(ftl_paths.len());
 
-    /// Use to iter all registered bundles and add functions or other
-    /// customizations.
-    pub fn iter_mut(&mut self) -> std::collections::hash_map::IterMut<LanguageIdentifier, Bundle> {
-        self.locales.iter_mut()
-    }
-}
+        for path in ftl_paths {
+            let ftl = Self::read_file(path)?;
 
-pub trait MessageKey {
-    fn key(&self) -> &str;
+            resources.push(Self::parse_resource(path, ftl)?);
+        }
 
-    fn attribute(&self) -> Option<&str> {
-        None
+        Ok(resources)
     }
-}
 
-impl<S: AsRef<str> + ?Sized> MessageKey for S {
-    fn key(&self) -> &str {
-        self.as_ref()
+    fn read_file<P>(path: &P) -> Result<String, LocalizerError>
+    where
+        P: AsRef<Path>,
+    {
+        std::fs::read_to_string(path).map_err(|err| LocalizerError::Io {
+            path: path.as_ref().to_owned(),
+            source: err,
+        })
     }
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct MessageAttribute<'key, 'attribute> {
-    pub key: &'key str,
-    pub attribute: &'attribute str,
-}
 
-impl<'key, 'attribute> MessageKey for MessageAttribute<'key, 'attribute> {
-    fn key(&self) -> &str {
-        self.key
+    fn parse_resource<P>(path: &P, ftl: String) -> Result<FluentResource, LocalizerError>
+    where
+        P: AsRef<Path>,
+    {
+        FluentResource::try_new(ftl).map_err(|(resource, errors)| LocalizerError::Parse {
+            path: path.as_ref().to_owned(),
+            errors,
+            ftl: resource.source().to_string(),
+        })
     }
 
-    fn attribute(&self) -> Option<&str> {
-        Some(self.attribute)
-    }
-}
+    /// Adds a bundle like [Localizer::add_bundle], keeping the valid entries of FTL files that
+    /// failed to parse instead of failing.
+    ///
+    /// The parse errors are returned, and also emitted as warnings with the `tracing` feature.
+    /// Files that cannot be read still fail the load.
+    pub fn add_bundle_lenient<P>(
+        &mut self,
+        locale: LanguageIdentifier,
+        ftl_paths: &[P],
+    ) -> Result<Vec<LocalizerError>, LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let mut resources = Vec::with_capacity(ftl_paths.len());
+        let mut warnings = Vec::new();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use unic_langid::langid;
+        for path in ftl_paths {
+            let ftl = Self::read_file(path)?;
 
-    const ENGLISH: LanguageIdentifier = langid!("en");
-    const JAPANESE: LanguageIdentifier = langid!("ja");
-    const MAIN: &str = "test_data/main.ftl";
-    const SUB: &str = "test_data/sub.ftl";
+            let resource = FluentResource::try_new(ftl).unwrap_or_else(|(resource, errors)| {
+                let warning = LocalizerError::Parse {
+                    path: path.as_ref().to_owned(),
+                    errors,
+                    ftl: resource.source().to_string(),
+                };
+                #[cfg(feature = "tracing")]
+                tracing::warn!(locale = %locale, error = %warning, "ignored invalid FTL entries");
+                warnings.push(warning);
 
-    #[test]
-    fn can_add_bundles() {
-        let mut loc = Localizer::new();
-        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
-        loc.add_bundle(JAPANESE, &[MAIN, SUB]).unwrap();
-    }
+                resource
+            });
 
-    #[test]
-    fn can_get_bundles() {
-        let mut loc = Localizer::new();
-        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
-        loc.add_bundle(JAPANESE, &[MAIN, SUB]).unwrap();
+            resources.push(resource);
+        }
 
-        let bundle = loc.get_locale(&ENGLISH);
+        let bundle = self.build_bundle(&locale, resources);
+        self.insert_bundle(locale, bundle);
 
-        assert!(bundle.is_some());
+        Ok(warnings)
     }
 
-    #[test]
-    fn can_get_bundle_on_lang() {
-        let mut loc = Localizer::new();
-        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
-        loc.add_bundle(JAPANESE, &[MAIN, SUB]).unwrap();
+    /// Adds a bundle like [Localizer::add_bundle], reading the FTL files with `tokio::fs`.
+    ///
+    /// Parsing is still synchronous. The bundle already registered for the locale is replaced.
+    #[cfg(feature = "tokio")]
+    pub async fn add_bundle_async<P>(
+        &mut self,
+        locale: LanguageIdentifier,
+        ftl_paths: &[P],
+    ) -> Result<(), LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let mut resources = Vec::with_capacity(ftl_paths.len());
 
-        let bundle = loc.get_locale(&langid!("en-US"));
+        for path in ftl_paths {
+            let ftl = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|err| LocalizerError::Io {
+                    path: path.as_ref().to_owned(),
+                    source: err,
+                })?;
 
-        assert!(bundle.is_some());
-    }
+            resources.push(Self::parse_resource(path, ftl)?);
+        }
 
-    #[test]
-    fn compiles_with_borrowed_string() {
-        let mut loc = Localizer::new();
-        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+        let mut bundle = self.build_bundle(&locale, resources);
+        bundle.paths = Some(to_path_bufs(ftl_paths));
+        self.insert_bundle(locale, bundle);
 
-        loc.format_message(&ENGLISH, &"test-key-a".to_owned(), None);
+        Ok(())
+    }
+
+    /// Adds the FTL files to the bundle already registered for the locale, creating the bundle
+    /// if there is none.
+    ///
+    /// Unlike [Localizer::add_bundle], which replaces the bundle of the locale, the existing
+    /// messages are kept. Messages in the new files override existing messages with the same
+    /// keys, Ex. to layer plugin translations loaded later onto the base translations.
+    ///
+    /// The bundle is only extended if all files could be read and parsed.
+    pub fn extend_bundle<P>(
+        &mut self,
+        locale: LanguageIdentifier,
+        ftl_paths: &[P],
+    ) -> Result<(), LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let resources = Self::read_resources(ftl_paths)?;
+
+        let Some(bundle) = self.locales.get_mut(&locale) else {
+            let mut bundle = self.build_bundle(&locale, resources);
+            bundle.paths = Some(to_path_bufs(ftl_paths));
+            self.insert_bundle(locale, bundle);
+
+            return Ok(());
+        };
+
+        if let Some(paths) = self.bundle_paths.get_mut(&locale) {
+            paths.extend(to_path_bufs(ftl_paths));
+        }
+
+        let message_keys = self.message_keys.entry(locale).or_default();
+        for resource in resources {
+            message_keys.extend(resource_message_keys(&resource));
+            bundle.add_resource_overriding(resource);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a bundle from parsed resources, later resources overriding earlier ones
+    fn build_bundle(
+        &self,
+        locale: &LanguageIdentifier,
+        resources: Vec<FluentResource>,
+    ) -> LoadedBundle {
+        let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
+        bundle.set_use_isolating(self.use_isolating);
+        for (name, func) in &self.functions {
+            // Names are unique and the bundle is empty, so this cannot fail
+            let _ = add_shared_function(&mut bundle, name, func);
+        }
+        let mut message_keys = BTreeSet::new();
+
+        for resource in resources {
+            message_keys.extend(resource_message_keys(&resource));
+
+            bundle.add_resource_overriding(resource);
+        }
+
+        LoadedBundle {
+            bundle,
+            message_keys,
+            paths: None,
+        }
+    }
+
+    /// Registers a bundle, replacing any bundle previously registered for the locale
+    pub(crate) fn insert_bundle(&mut self, locale: LanguageIdentifier, bundle: LoadedBundle) {
+        self.message_keys
+            .insert(locale.clone(), bundle.message_keys);
+        match bundle.paths {
+            Some(paths) => self.bundle_paths.insert(locale.clone(), paths),
+            None => self.bundle_paths.remove(&locale),
+        };
+
+        // Keep the first locale of the language in alphabetical order as its fallback
+        self.language_fallbacks
+            .entry(locale.language)
+            .and_modify(|fallback| {
+                if locale.to_string() < fallback.to_string() {
+                    *fallback = locale.clone();
+                }
+            })
+            .or_insert_with(|| locale.clone());
+
+        self.locales.insert(locale, bundle.bundle);
+    }
+
+    /// Adds a bundle to the localizer from FTL source strings, for example those embedded
+    /// with `include_str!`
+    ///
+    /// Override semantics match [Localizer::add_bundle]: later sources override messages with
+    /// the same keys in earlier ones.
+    pub fn add_bundle_from_sources(
+        &mut self,
+        locale: LanguageIdentifier,
+        sources: &[&str],
+    ) -> Result<(), LocalizerError> {
+        let mut resources = Vec::with_capacity(sources.len());
+
+        for (i, source) in sources.iter().enumerate() {
+            let ftl = FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+                LocalizerError::ParseSource {
+                    index: i,
+                    errors,
+                    ftl: source.to_string(),
+                }
+            })?;
+
+            resources.push(ftl);
+        }
+
+        let bundle = self.build_bundle(&locale, resources);
+        self.insert_bundle(locale, bundle);
+
+        Ok(())
+    }
+
+    /// Adds a bundle to the localizer including all the FTL files found in the given directory
+    ///
+    /// Only files with the `.ftl` extension directly inside the directory are loaded. Files are
+    /// added in filename order, so later files override messages with the same keys in earlier ones.
+    pub fn add_bundle_from_dir<P>(
+        &mut self,
+        locale: LanguageIdentifier,
+        dir: P,
+    ) -> Result<(), LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let io_error = |err| LocalizerError::Io {
+            path: dir.as_ref().to_owned(),
+            source: err,
+        };
+        let entries = std::fs::read_dir(&dir).map_err(io_error)?;
+
+        let mut ftl_paths = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(io_error)?.path();
+
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "ftl") {
+                ftl_paths.push(path);
+            }
+        }
+        ftl_paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+        self.add_bundle(locale, &ftl_paths)
+    }
+
+    /// Adds a bundle to the localizer including the FTL files of a zip archive whose names
+    /// match a glob pattern, Ex. `ja/*.ftl`, without unpacking the archive.
+    ///
+    /// Patterns with a `/` are matched against the full entry name, others against the file
+    /// name only. `*` matches within a path segment and `**` across segments.
+    /// Entries are added in name order, so later entries override messages with the same keys
+    /// in earlier ones.
+    ///
+    /// Errors reading or parsing an entry name it as a path inside the archive, Ex.
+    /// `locales.zip/ja/main.ftl`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// localizer.add_bundle_from_zip(JAPANESE, "locales.zip", "ja/*.ftl")?;
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn add_bundle_from_zip<P>(
+        &mut self,
+        locale: LanguageIdentifier,
+        archive_path: P,
+        inner_glob: &str,
+    ) -> Result<(), LocalizerError>
+    where
+        P: AsRef<Path>,
+    {
+        use std::io::Read;
+
+        let archive_path = archive_path.as_ref();
+        let zip_error = |source| LocalizerError::Zip {
+            path: archive_path.to_owned(),
+            source,
+        };
+        let file = std::fs::File::open(archive_path).map_err(|err| LocalizerError::Io {
+            path: archive_path.to_owned(),
+            source: err,
+        })?;
+        let mut archive = zip::ZipArchive::new(file).map_err(zip_error)?;
+
+        let mut names = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/') && zip_entry_matches(inner_glob, name))
+            .map(String::from)
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let mut resources = Vec::with_capacity(names.len());
+        for name in names {
+            let entry_path = archive_path.join(&name);
+            let mut ftl = String::new();
+            archive
+                .by_name(&name)
+                .map_err(zip_error)?
+                .read_to_string(&mut ftl)
+                .map_err(|err| LocalizerError::Io {
+                    path: entry_path.clone(),
+                    source: err,
+                })?;
+
+            resources.push(Self::parse_resource(&entry_path, ftl)?);
+        }
+
+        let bundle = self.build_bundle(&locale, resources);
+        self.insert_bundle(locale, bundle);
+
+        Ok(())
+    }
+
+    /// Searches for a full locale match and returns it.
+    /// If no full locale match, returns a language match if available.
+    ///
+    /// Partial matches drop the least significant subtags first, so that the most specific
+    /// bundle is preferred: `zh-Hant-TW` tries `zh-Hant`, then `zh-TW`, then the bare `zh`,
+    /// then the first `zh-Hant` variant in sorted order (ex. `zh-Hant-HK`).
+    ///
+    /// Language matches are resolved deterministically: the bundle registered for the bare
+    /// language (ex. `en`) is preferred, followed by the first regional variant in sorted
+    /// order (ex. `en-AU` before `en-US`).
+    ///
+    /// Equivalent languages are tried last if enabled, see [Localizer::set_macrolanguage_fallback].
+    ///
+    /// The fallback bundle is returned if no bundle matches, see
+    /// [Localizer::add_fallback_bundle].
+    pub fn get_locale(&self, locale: &LanguageIdentifier) -> Option<&Bundle> {
+        self.resolve_locale(locale).map(|(_, bundle)| bundle)
+    }
+
+    /// Returns the bundle matching the locale as in [Localizer::get_locale], with the locale it
+    /// was registered for, Ex. `en` for `en-GB`, to set the `Content-Language` of a response.
+    ///
+    /// # Example
+    /// ```ignore
+    /// if let Some((served, _bundle)) = localizer.resolve_locale(&langid!("en-GB")) {
+    ///     assert_eq!(served, &langid!("en"));
+    /// }
+    /// ```
+    pub fn resolve_locale(
+        &self,
+        locale: &LanguageIdentifier,
+    ) -> Option<(&LanguageIdentifier, &Bundle)> {
+        self.matching_locale(locale)
+            .and_then(|key| self.locales.get_key_value(key))
+            .or_else(|| {
+                self.fallback_bundle
+                    .as_ref()
+                    .map(|(locale, fallback)| (locale, &fallback.bundle))
+            })
+    }
+
+    /// Checks a registered bundle matches the locale, as in [Localizer::get_locale], without
+    /// the fallback bundle
+    pub fn has_locale(&self, locale: &LanguageIdentifier) -> bool {
+        self.matching_locale(locale).is_some()
+    }
+
+    /// Lists the locales of the registered bundles, sorted by their tags
+    pub fn available_locales(&self) -> Vec<LanguageIdentifier> {
+        let mut locales = self.locales.keys().cloned().collect::<Vec<_>>();
+        locales.sort_by_key(|locale| locale.to_string());
+
+        locales
+    }
+
+    /// Returns the registered locale that [Localizer::get_locale] would match
+    fn matching_locale(&self, locale: &LanguageIdentifier) -> Option<&LanguageIdentifier> {
+        let matched = self.matching_language(locale);
+        if matched.is_some() || !self.macrolanguage_fallback {
+            return matched;
+        }
+
+        equivalent_languages(locale.language).find_map(|language| {
+            let mut equivalent = locale.clone();
+            equivalent.language = language;
+
+            self.matching_language(&equivalent)
+        })
+    }
+
+    fn matching_language(&self, locale: &LanguageIdentifier) -> Option<&LanguageIdentifier> {
+        let full_locale_match = self.locales.get_key_value(locale).map(|(key, _)| key);
+
+        #[cfg(feature = "negotiation")]
+        if let (None, Some(strategy)) = (full_locale_match, self.negotiation) {
+            // Sort the available locales, as negotiation prefers the first match
+            let mut available = self.locales.keys().collect::<Vec<_>>();
+            available.sort_by_key(|key| key.to_string());
+
+            return fluent_langneg::negotiate::filter_matches(
+                std::slice::from_ref(locale),
+                &available,
+                strategy,
+            )
+            .first()
+            .map(|key| **key);
+        }
+
+        // Try to match only on the language if full match not found
+        match full_locale_match {
+            Some(l) => Some(l),
+            None => {
+                // Drop the least significant subtags first, Ex. `zh-Hant-TW` tries `zh-Hant`
+                // then `zh-TW` then `zh`
+                let script_only = locale.script.map(|script| {
+                    LanguageIdentifier::from_parts(locale.language, Some(script), None, &[])
+                });
+                let region_only = locale.region.map(|region| {
+                    LanguageIdentifier::from_parts(locale.language, None, Some(region), &[])
+                });
+                let language_only =
+                    LanguageIdentifier::from_parts(locale.language, None, None, &[]);
+
+                script_only
+                    .iter()
+                    .chain(region_only.iter())
+                    .chain(std::iter::once(&language_only))
+                    .find_map(|candidate| self.locales.get_key_value(candidate))
+                    .map(|(key, _)| key)
+                    .or_else(|| self.script_fallback(locale))
+                    .or_else(|| self.language_fallbacks.get(&locale.language))
+            }
+        }
+    }
+
+    /// Returns the first registered locale in alphabetical order with the language and script of
+    /// the locale, Ex. `zh-Hant-HK` for `zh-Hant-TW`
+    fn script_fallback(&self, locale: &LanguageIdentifier) -> Option<&LanguageIdentifier> {
+        locale.script?;
+
+        self.locales
+            .keys()
+            .filter(|key| key.language == locale.language && key.script == locale.script)
+            .min_by_key(|key| key.to_string())
+    }
+
+    /// Lists the registered locales with their native names, Ex. for a language dropdown,
+    /// flagging the locale matched by `current` as in [Localizer::get_locale].
+    ///
+    /// Names are formatted from the `-language-name` term of each bundle, Ex.
+    /// `-language-name = 日本語`, defaulting to the locale tag. Locales are sorted by their tags.
+    ///
+    /// # Example
+    /// ```ignore
+    /// for option in localizer.language_switcher(&lang) {
+    ///     println!("{} {} {}", option.locale, option.name, option.current);
+    /// }
+    /// ```
+    pub fn language_switcher(&self, current: &LanguageIdentifier) -> Vec<LanguageOption> {
+        let current = self.matching_locale(current);
+
+        self.available_locales()
+            .into_iter()
+            .map(|locale| {
+                let name = self
+                    .format_bundle_message(&locale, LANGUAGE_NAME_TERM, None)
+                    .map(|(name, _)| name.into_owned())
+                    .unwrap_or_else(|_| locale.to_string());
+
+                LanguageOption {
+                    current: current == Some(&locale),
+                    locale,
+                    name,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the FTL source of a message in the bundle matched by [Localizer::get_locale],
+    /// Ex. to show it next to the formatted message in a review tool.
+    ///
+    /// Bundles only keep the parsed messages, so the source is serialized back from them:
+    /// comments are dropped and multiline patterns are indented by four spaces. Terms are not
+    /// exposed by Fluent and are never found.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // test-name = Peg { $name }
+    /// let source = localizer.get_message_source(&ENGLISH, "test-name");
+    /// let formatted = localizer.format_message(&ENGLISH, "test-name", Some(&args));
+    ///
+    /// assert_eq!(source.as_deref(), Some("test-name = Peg { $name }"));
+    /// ```
+    pub fn get_message_source(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+    ) -> Option<String> {
+        let message = self.get_locale(locale)?.get_message(key.key())?;
+
+        match key.attribute() {
+            Some(attribute) => {
+                let attribute = message.get_attribute(attribute)?;
+
+                Some(crate::serialize::serialize_attribute(
+                    attribute.id(),
+                    attribute.value(),
+                ))
+            }
+            None => Some(crate::serialize::serialize_message(key.key(), &message)),
+        }
+    }
+
+    /// Returns the bundle that formats a message as in [Localizer::format_message], trying
+    /// the fallback chain and the fallback bundle when the matched bundle does not have it
+    #[cfg(feature = "tera")]
+    pub(crate) fn message_bundle(&self, locale: &LanguageIdentifier, key: &str) -> Option<&Bundle> {
+        let has_message = |bundle: &&Bundle| bundle.has_message(key);
+
+        self.get_locale(locale)
+            .filter(has_message)
+            .or_else(|| {
+                self.fallbacks(locale)
+                    .iter()
+                    .find_map(|fallback| self.get_locale(fallback).filter(has_message))
+            })
+            .or_else(|| {
+                self.fallback_bundle
+                    .as_ref()
+                    .map(|(_, fallback)| &fallback.bundle)
+                    .filter(has_message)
+            })
+    }
+
+    /// Lists the message identifiers of the bundle matched by [Localizer::get_locale], in sorted order.
+    ///
+    /// Terms are listed with their `-` prefix. Only messages loaded through the `add_bundle`
+    /// methods are listed.
+    pub fn message_keys(&self, locale: &LanguageIdentifier) -> Option<Vec<String>> {
+        self.matching_locale(locale)
+            .and_then(|key| self.message_keys.get(key))
+            .map(|keys| keys.iter().cloned().collect())
+    }
+
+    /// Lists, for every registered locale other than the reference, the message keys found in the
+    /// reference locale's bundle but missing from that locale's bundle.
+    ///
+    /// Returns None if no bundle matches the reference locale.
+    pub fn coverage_report(
+        &self,
+        reference: &LanguageIdentifier,
+    ) -> Option<HashMap<LanguageIdentifier, Vec<String>>> {
+        let reference = self.matching_locale(reference)?;
+        let reference_keys = self.message_keys.get(reference)?;
+
+        let report = self
+            .message_keys
+            .iter()
+            .filter(|(locale, _)| *locale != reference)
+            .map(|(locale, keys)| {
+                let missing = reference_keys.difference(keys).cloned().collect();
+
+                (locale.clone(), missing)
+            })
+            .collect();
+
+        Some(report)
+    }
+
+    /// Format a FTL message into target locale if available.<br>
+    /// See Fluent RS [FluentBundle::format_pattern documentation](https://docs.rs/fluent/latest/fluent/bundle/struct.FluentBundle.html#method.format_pattern)
+    /// for details
+    ///
+    /// Fluent template errors are printed to stdout.
+    pub fn format_message(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&FluentArgs>,
+    ) -> Option<String> {
+        self.format_message_result(locale, key, args).ok()
+    }
+
+    /// Format a FTL message into target locale if available, applying the number options
+    /// of the localizer to the numeric arguments, as for tera.
+    ///
+    /// Numbers with options other than the defaults, Ex. from [FluentNumber::new], keep their
+    /// own options.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let localizer = localizer.set_fluent_number_options(FluentNumberOptions {
+    ///     minimum_fraction_digits: Some(2),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let mut args = FluentArgs::new();
+    /// args.set("number", 5);
+    ///
+    /// // 5.00 new messages
+    /// let message = localizer.format_message_with_numbers(&ENGLISH, "test-key-b", Some(&args));
+    /// ```
+    pub fn format_message_with_numbers(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&FluentArgs>,
+    ) -> Option<String> {
+        let args = args.map(|args| self.apply_number_options(args));
+
+        self.format_message(locale, key, args.as_ref())
+    }
+
+    /// Format a FTL message into target locale if available, setting the `count` argument to a
+    /// number with the number options of the localizer, for use in plural selectors.
+    ///
+    /// The other arguments are passed as is, and a `count` argument among them is replaced.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // emails = { $count ->
+    /// //     [one] You have one email
+    /// //    *[other] You have { $count } emails
+    /// // }
+    /// let message = localizer.format_plural(&ENGLISH, "emails", 3, None);
+    /// ```
+    pub fn format_plural(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        count: impl Into<FluentNumber>,
+        args: Option<&FluentArgs>,
+    ) -> Option<String> {
+        let mut plural_args = args
+            .map(|args| {
+                args.iter()
+                    .map(|(key, value)| (key, value.clone()))
+                    .collect::<FluentArgs>()
+            })
+            .unwrap_or_default();
+        plural_args.set(
+            "count",
+            FluentNumber::new(count.into().value, self.number_options.clone()),
+        );
+
+        self.format_message(locale, key, Some(&plural_args))
+    }
+
+    /// Finds the CLDR plural category of a number in a locale, Ex. `ONE` for 1 and `OTHER` for 2
+    /// in English, as matched by the plural selectors of FTL messages.
+    ///
+    /// The number uses the number options of the localizer, so `1` is `OTHER` in English with
+    /// a minimum of one fraction digit. Locales without plural rules, and numbers that are not
+    /// finite, return `OTHER`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// assert_eq!(localizer.plural_category(&ENGLISH, 1), PluralCategory::ONE);
+    /// ```
+    pub fn plural_category(
+        &self,
+        locale: &LanguageIdentifier,
+        number: impl Into<FluentNumber>,
+    ) -> PluralCategory {
+        let rules = PluralRules::create(locale.clone(), PluralRuleType::CARDINAL).or_else(|_| {
+            PluralRules::create(
+                LanguageIdentifier::from_parts(locale.language, None, None, &[]),
+                PluralRuleType::CARDINAL,
+            )
+        });
+        let number = FluentNumber::new(number.into().value, self.number_options.clone());
+
+        match rules {
+            Ok(rules) if number.value.is_finite() => rules
+                .select(PluralOperands::from(&number))
+                .unwrap_or(PluralCategory::OTHER),
+            _ => PluralCategory::OTHER,
+        }
+    }
+
+    /// Sets the number options of the localizer on the numbers of the arguments with default
+    /// options
+    fn apply_number_options<'a>(&self, args: &'a FluentArgs) -> FluentArgs<'a> {
+        let default_options = FluentNumberOptions::default();
+
+        args.iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    FluentValue::Number(number) if number.options == default_options => {
+                        FluentValue::Number(FluentNumber::new(
+                            number.value,
+                            self.number_options.clone(),
+                        ))
+                    }
+                    value => value.clone(),
+                };
+
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// Formats a timestamp with the numeric date and time conventions of a locale, Ex.
+    /// `3/15/2024` in `en-US` and `2024/03/15` in `ja`, in the time zone of the timestamp.
+    ///
+    /// Locales without known conventions use ISO 8601, Ex. `2024-03-15 14:30`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let timestamp = chrono::DateTime::from_timestamp(1710513000, 0).unwrap();
+    ///
+    /// let date = localizer.format_datetime(&JAPANESE, &timestamp, DateTimeStyle::Date);
+    /// ```
+    #[cfg(feature = "datetime")]
+    pub fn format_datetime<Tz>(
+        &self,
+        locale: &LanguageIdentifier,
+        timestamp: &chrono::DateTime<Tz>,
+        style: crate::DateTimeStyle,
+    ) -> String
+    where
+        Tz: chrono::TimeZone,
+        Tz::Offset: std::fmt::Display,
+    {
+        crate::datetime::format_datetime(locale, timestamp, style)
+    }
+
+    /// Finds the name of a locale displayed in another locale from the CLDR data, Ex.
+    /// `Japanese` for `ja` in `en`, for a language picker in the current language of the user.
+    /// See [Localizer::language_switcher] for the native names of the bundles.
+    ///
+    /// Returns None if the display locale has no data, or the language has no name in it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let name = localizer.display_name(&JAPANESE, &ENGLISH);
+    ///
+    /// assert_eq!(name.as_deref(), Some("Japanese"));
+    /// ```
+    #[cfg(feature = "displaynames")]
+    pub fn display_name(
+        &self,
+        target: &LanguageIdentifier,
+        in_locale: &LanguageIdentifier,
+    ) -> Option<String> {
+        crate::displaynames::display_name(target, in_locale)
+    }
+
+    /// Format a FTL message into the default locale if available.
+    ///
+    /// Returns None if no default locale is set, or if it has no matching bundle or message.
+    pub fn format_default(
+        &self,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&FluentArgs>,
+    ) -> Option<String> {
+        self.format_message(self.default_locale.as_ref()?, key, args)
+    }
+
+    /// Format a FTL message into target locale if available, with arguments from a map of
+    /// strings or fluent values.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let args = HashMap::from([("name", "Bob")]);
+    ///
+    /// let message = localizer.format_message_map(&ENGLISH, "test-name", &args);
+    /// ```
+    pub fn format_message_map<'a, K, V>(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: &'a HashMap<K, V>,
+    ) -> Option<String>
+    where
+        K: AsRef<str>,
+        V: Clone + Into<FluentValue<'a>>,
+    {
+        let args = args
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value.clone()))
+            .collect::<FluentArgs>();
+
+        self.format_message(locale, key, Some(&args))
+    }
+
+    /// Format a FTL message into target locale if available.<br>
+    /// See Fluent RS [FluentBundle::format_pattern documentation](https://docs.rs/fluent/latest/fluent/bundle/struct.FluentBundle.html#method.format_pattern)
+    /// for details
+    ///
+    /// Fluent template errors are printed to stdout.
+    pub fn format_message_result(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&FluentArgs>,
+    ) -> Result<String, LocalizerError> {
+        let (message, errors) = self.format_message_verbose(locale, key, args)?;
+        report_errors(locale, errors);
+
+        Ok(message)
+    }
+
+    /// Format a FTL message into target locale if available, borrowing the message from the
+    /// bundle when possible.
+    ///
+    /// Fluent only borrows messages made of a single text element, so messages with
+    /// placeables, isolated arguments or terms are still allocated.
+    ///
+    /// Fluent template errors are printed to stdout.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // test-key-a = Hello World
+    /// let message = localizer.format_message_cow(&ENGLISH, "test-key-a", None);
+    ///
+    /// assert!(matches!(message, Some(Cow::Borrowed("Hello World"))));
+    /// ```
+    pub fn format_message_cow<'a>(
+        &'a self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&'a FluentArgs>,
+    ) -> Option<Cow<'a, str>> {
+        let (message, errors) = self.format_message_cow_verbose(locale, key, args).ok()?;
+        report_errors(locale, errors);
+
+        Some(message)
+    }
+
+    /// Format several FTL messages into target locale, matching the bundle only once.
+    ///
+    /// Each message is None if it is not available, and the fallback chain is tried for each
+    /// missing message as in [Localizer::format_message].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let messages = localizer.format_messages(&ENGLISH, &["nav-home", "nav-about"]);
+    ///
+    /// ctx.insert("messages", &messages);
+    /// ```
+    pub fn format_messages(
+        &self,
+        locale: &LanguageIdentifier,
+        keys: &[&str],
+    ) -> HashMap<String, Option<String>> {
+        let matched = self.matching_locale(locale);
+
+        keys.iter()
+            .map(|key| {
+                let result = match matched {
+                    Some(matched) => self.format_bundle_message(matched, *key, None),
+                    None => Err(LocalizerError::LocaleNotFound(locale.clone())),
+                };
+                let message = self
+                    .or_fallback(locale, *key, None, result)
+                    .map(|(message, errors)| {
+                        report_errors(locale, errors);
+
+                        message.into_owned()
+                    })
+                    .ok();
+
+                (key.to_string(), message)
+            })
+            .collect()
+    }
+
+    /// Format a FTL message into target locale if available, returning the message along with
+    /// the Fluent errors encountered while formatting.<br>
+    /// If the locale or message is not found, the locales of the fallback chain are tried in
+    /// order, see [Localizer::set_fallback_chain].<br>
+    /// See Fluent RS [FluentBundle::format_pattern documentation](https://docs.rs/fluent/latest/fluent/bundle/struct.FluentBundle.html#method.format_pattern)
+    /// for details
+    ///
+    /// Fluent replaces the parts of a pattern that failed to format with fallback values, so the
+    /// message may still be usable when errors are returned.
+    pub fn format_message_verbose(
+        &self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&FluentArgs>,
+    ) -> Result<(String, Vec<FluentError>), LocalizerError> {
+        self.format_message_cow_verbose(locale, key, args)
+            .map(|(message, errors)| (message.into_owned(), errors))
+    }
+
+    fn format_message_cow_verbose<'a>(
+        &'a self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&'a FluentArgs>,
+    ) -> Result<(Cow<'a, str>, Vec<FluentError>), LocalizerError> {
+        let result = self.format_locale_message(locale, key, args);
+
+        self.or_fallback(locale, key, args, result)
+    }
+
+    /// Tries the fallback chain of the requested locale, then the fallback bundle, if the locale
+    /// or message was not found
+    fn or_fallback<'a>(
+        &'a self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&'a FluentArgs>,
+        result: Result<(Cow<'a, str>, Vec<FluentError>), LocalizerError>,
+    ) -> Result<(Cow<'a, str>, Vec<FluentError>), LocalizerError> {
+        match result {
+            Err(
+                err @ (LocalizerError::LocaleNotFound(_) | LocalizerError::MessageNotFound { .. }),
+            ) => self
+                .fallbacks(locale)
+                .iter()
+                .find_map(|fallback| self.format_locale_message(fallback, key, args).ok())
+                .or_else(|| {
+                    let (_, fallback) = self.fallback_bundle.as_ref()?;
+
+                    self.format_from_bundle(
+                        &fallback.bundle,
+                        Some(&fallback.message_keys),
+                        key,
+                        args,
+                    )
+                    .ok()
+                })
+                .ok_or(err),
+            result => result,
+        }
+    }
+
+    /// The fallback chain of the requested locale, or of the locale it matched
+    fn fallbacks(&self, locale: &LanguageIdentifier) -> &[LanguageIdentifier] {
+        self.fallback_chain
+            .get(locale)
+            .or_else(|| {
+                self.matching_locale(locale)
+                    .and_then(|key| self.fallback_chain.get(key))
+            })
+            .map(|chain| chain.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Formats a message from the bundle matching the locale, without fallbacks
+    fn format_locale_message<'a>(
+        &'a self,
+        locale: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&'a FluentArgs>,
+    ) -> Result<(Cow<'a, str>, Vec<FluentError>), LocalizerError> {
+        let matched = self
+            .matching_locale(locale)
+            .ok_or_else(|| LocalizerError::LocaleNotFound(locale.clone()))?;
+
+        self.format_bundle_message(matched, key, args)
+    }
+
+    /// Formats a message from the bundle of a registered locale
+    fn format_bundle_message<'a>(
+        &'a self,
+        matched: &LanguageIdentifier,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&'a FluentArgs>,
+    ) -> Result<(Cow<'a, str>, Vec<FluentError>), LocalizerError> {
+        self.format_from_bundle(
+            &self.locales[matched],
+            self.message_keys.get(matched),
+            key,
+            args,
+        )
+    }
+
+    /// Formats a message from a bundle, with the message keys of the bundle to find its terms
+    fn format_from_bundle<'a>(
+        &self,
+        bundle: &'a Bundle,
+        message_keys: Option<&BTreeSet<String>>,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&'a FluentArgs>,
+    ) -> Result<(Cow<'a, str>, Vec<FluentError>), LocalizerError> {
+        if key.key().starts_with('-') {
+            return Self::format_term(bundle, message_keys, key);
+        }
+
+        let message =
+            bundle
+                .get_message(key.key())
+                .ok_or_else(|| LocalizerError::MessageNotFound {
+                    key: key.key().to_string(),
+                    attribute: None,
+                })?;
+
+        let mut errors = Vec::new();
+
+        let message = if let Some(attribute) = key.attribute() {
+            let attribute = message.get_attribute(attribute).ok_or_else(|| {
+                LocalizerError::MessageNotFound {
+                    key: key.key().to_string(),
+                    attribute: Some(attribute.to_string()),
+                }
+            })?;
+
+            bundle.format_pattern(attribute.value(), args, &mut errors)
+        } else {
+            bundle.format_pattern(
+                message
+                    .value()
+                    .ok_or_else(|| LocalizerError::MissingValue {
+                        key: key.key().to_string(),
+                    })?,
+                args,
+                &mut errors,
+            )
+        };
+
+        Ok((message, errors))
+    }
+
+    /// Formats a term through a message referencing it, as bundles do not expose their terms.
+    ///
+    /// Term attributes are private in Fluent and can only be used as selectors, so they are
+    /// never found.
+    fn format_term(
+        bundle: &Bundle,
+        message_keys: Option<&BTreeSet<String>>,
+        key: &(impl MessageKey + ?Sized),
+    ) -> Result<(Cow<'static, str>, Vec<FluentError>), LocalizerError> {
+        let not_found = || LocalizerError::MessageNotFound {
+            key: key.key().to_string(),
+            attribute: key.attribute().map(str::to_string),
+        };
+
+        // Only reference known terms, so that the key is a valid identifier
+        let is_known = message_keys.is_some_and(|keys| keys.contains(key.key()));
+        if key.attribute().is_some() || !is_known {
+            return Err(not_found());
+        }
+
+        let reference = FluentResource::try_new(format!("term = {{ {} }}", key.key()))
+            .map_err(|_| not_found())?;
+        let Some(ast::Entry::Message(ast::Message {
+            value: Some(pattern),
+            ..
+        })) = reference.entries().next()
+        else {
+            return Err(not_found());
+        };
+
+        let mut errors = Vec::new();
+        let term = bundle
+            .format_pattern(pattern, None, &mut errors)
+            .into_owned();
+
+        Ok((Cow::Owned(term), errors))
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, LanguageIdentifier, Bundle> {
+        self.locales.iter()
+    }
+
+    /// Use to iter all registered bundles and add functions or other
+    /// customizations.
+    pub fn iter_mut(
+        &mut self,
+    ) -> std::collections::hash_map::IterMut<'_, LanguageIdentifier, Bundle> {
+        self.locales.iter_mut()
+    }
+}
+
+/// The term holding the native name of the language of a bundle, see
+/// [Localizer::language_switcher]
+const LANGUAGE_NAME_TERM: &str = "-language-name";
+
+/// A locale listed by [Localizer::language_switcher]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageOption {
+    pub locale: LanguageIdentifier,
+    /// The native name of the language, Ex. `日本語`
+    pub name: String,
+    /// Whether the locale matches the current one
+    pub current: bool,
+}
+
+/// The key of a message, and optionally one of its attributes.
+///
+/// Keys starting with `-` are resolved as terms, ex. `-brand-name`.
+pub trait MessageKey {
+    fn key(&self) -> &str;
+
+    fn attribute(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<S: AsRef<str> + ?Sized> MessageKey for S {
+    fn key(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MessageAttribute<'key, 'attribute> {
+    pub key: &'key str,
+    pub attribute: &'attribute str,
+}
+
+impl<'key, 'attribute> MessageKey for MessageAttribute<'key, 'attribute> {
+    fn key(&self) -> &str {
+        self.key
+    }
+
+    fn attribute(&self) -> Option<&str> {
+        Some(self.attribute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unic_langid::langid;
+
+    const ENGLISH: LanguageIdentifier = langid!("en");
+    const JAPANESE: LanguageIdentifier = langid!("ja");
+    const MAIN: &str = "test_data/main.ftl";
+    const SUB: &str = "test_data/sub.ftl";
+
+    #[test]
+    fn can_add_bundles() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+        loc.add_bundle(JAPANESE, &[MAIN, SUB]).unwrap();
+    }
+
+    #[test]
+    fn can_add_bundle_from_dir() {
+        let mut loc = Localizer::new();
+        loc.add_bundle_from_dir(ENGLISH, "test_data").unwrap();
+
+        assert_eq!(
+            Some(String::from("Hello World")),
+            loc.format_message(&ENGLISH, "test-key-a", None)
+        );
+        assert!(loc.format_message(&ENGLISH, "test-key-b", None).is_some());
+    }
+
+    #[test]
+    fn add_bundle_from_missing_dir_fails() {
+        let mut loc = Localizer::new();
+
+        assert!(loc
+            .add_bundle_from_dir(ENGLISH, "test_data/does_not_exist")
+            .is_err());
+    }
+
+    #[test]
+    fn can_add_bundle_from_sources() {
+        let mut loc = Localizer::new();
+        loc.add_bundle_from_sources(
+            ENGLISH,
+            &[
+                include_str!("../test_data/main.ftl"),
+                "test-key-a = Overridden",
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(String::from("Overridden")),
+            loc.format_message(&ENGLISH, "test-key-a", None)
+        );
+    }
+
+    #[test]
+    fn add_bundle_from_invalid_source_names_index() {
+        let mut loc = Localizer::new();
+        let err = loc
+            .add_bundle_from_sources(ENGLISH, &["test-key-a = Hello", "invalid {"])
+            .unwrap_err();
+
+        assert!(err.to_string().contains("index 1"));
+    }
+
+    #[test]
+    fn can_get_bundles() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+        loc.add_bundle(JAPANESE, &[MAIN, SUB]).unwrap();
+
+        let bundle = loc.get_locale(&ENGLISH);
+
+        assert!(bundle.is_some());
+    }
+
+    #[test]
+    fn can_get_bundle_on_lang() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+        loc.add_bundle(JAPANESE, &[MAIN, SUB]).unwrap();
+
+        let bundle = loc.get_locale(&langid!("en-US"));
+
+        assert!(bundle.is_some());
+    }
+
+    #[test]
+    fn language_fallback_prefers_bare_language() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("en-US"), &[MAIN]).unwrap();
+        loc.add_bundle(langid!("en-AU"), &[MAIN]).unwrap();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+
+        let bundle = loc.get_locale(&langid!("en-GB")).unwrap();
+
+        assert_eq!(bundle.locales, vec![ENGLISH]);
+    }
+
+    #[test]
+    fn language_fallback_prefers_sorted_region() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("en-US"), &[MAIN]).unwrap();
+        loc.add_bundle(langid!("en-AU"), &[MAIN]).unwrap();
+
+        let bundle = loc.get_locale(&langid!("en-GB")).unwrap();
+
+        assert_eq!(bundle.locales, vec![langid!("en-AU")]);
+    }
+
+    #[test]
+    fn can_list_message_keys() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+
+        let keys = loc.message_keys(&langid!("en-US")).unwrap();
+
+        assert_eq!(keys, vec!["attribute-test", "test-key-a", "test-name"]);
+        assert!(loc.message_keys(&JAPANESE).is_none());
+    }
+
+    #[test]
+    fn coverage_report_lists_missing_keys() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+        loc.add_bundle(JAPANESE, &[MAIN]).unwrap();
+        loc.add_bundle(langid!("fr"), &[MAIN, SUB]).unwrap();
+
+        let report = loc.coverage_report(&ENGLISH).unwrap();
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[&JAPANESE], vec!["test-key-b"]);
+        assert!(report[&langid!("fr")].is_empty());
+        assert!(loc.coverage_report(&langid!("de")).is_none());
+    }
+
+    #[test]
+    fn verbose_format_returns_errors() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        let (message, errors) = loc
+            .format_message_verbose(&ENGLISH, "test-name", None)
+            .unwrap();
+
+        assert_eq!("Peg \u{2068}{$name}\u{2069}", message);
+        assert_eq!(errors.len(), 1);
+
+        let (_, errors) = loc
+            .format_message_verbose(&ENGLISH, "test-key-a", None)
+            .unwrap();
+
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn errors_have_typed_causes() {
+        let mut loc = Localizer::new();
+
+        assert!(matches!(
+            loc.add_bundle(ENGLISH, &["test_data/does_not_exist.ftl"]),
+            Err(LocalizerError::Io { .. })
+        ));
+        assert!(matches!(
+            loc.add_bundle_from_sources(ENGLISH, &["invalid {"]),
+            Err(LocalizerError::ParseSource { index: 0, .. })
+        ));
+
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        assert!(matches!(
+            loc.format_message_result(&JAPANESE, "test-key-a", None),
+            Err(LocalizerError::LocaleNotFound(_))
+        ));
+
+        let err = loc
+            .format_message_result(&ENGLISH, "does-not-exist", None)
+            .unwrap_err();
+
+        assert!(matches!(err, LocalizerError::MessageNotFound { .. }));
+        assert_eq!(
+            "could not find message with key=does-not-exist",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn compiles_with_borrowed_string() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        loc.format_message(&ENGLISH, &"test-key-a".to_owned(), None);
     }
 
     #[test]
@@ -325,4 +1986,855 @@ mod tests {
 
         assert_eq!(Some(String::from("Peg \u{2068}Deadpool\u{2069}")), message);
     }
+
+    #[cfg(feature = "negotiation")]
+    #[test]
+    fn negotiation_matches_script_by_likely_subtags() {
+        let mut naive = Localizer::new();
+        naive
+            .add_bundle_from_sources(langid!("zh-Hans"), &["test-key-a = 简体"])
+            .unwrap();
+        naive
+            .add_bundle_from_sources(langid!("zh-Hant"), &["test-key-a = 繁體"])
+            .unwrap();
+
+        assert_eq!(
+            naive.format_message(&langid!("zh-TW"), "test-key-a", None),
+            Some(String::from("简体"))
+        );
+
+        let negotiated =
+            naive.set_negotiation_strategy(fluent_langneg::NegotiationStrategy::Filtering);
+
+        assert_eq!(
+            negotiated.format_message(&langid!("zh-TW"), "test-key-a", None),
+            Some(String::from("繁體"))
+        );
+        assert_eq!(
+            negotiated.format_message(&langid!("zh-CN"), "test-key-a", None),
+            Some(String::from("简体"))
+        );
+        assert!(negotiated.get_locale(&langid!("ja")).is_none());
+    }
+
+    #[test]
+    fn can_format_message_with_map_args() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        let args = HashMap::from([(String::from("name"), String::from("Bob"))]);
+        let message = loc.format_message_map(&ENGLISH, "test-name", &args);
+
+        assert_eq!(message, Some(String::from("Peg \u{2068}Bob\u{2069}")));
+
+        let args = HashMap::from([("name", FluentValue::from("Deadpool"))]);
+        let message = loc.format_message_map(&ENGLISH, "test-name", &args);
+
+        assert_eq!(message, Some(String::from("Peg \u{2068}Deadpool\u{2069}")));
+    }
+
+    #[test]
+    fn can_fall_back_to_chain() {
+        let mut loc = Localizer::new()
+            .set_fallback_chain(HashMap::from([(langid!("fr"), vec![JAPANESE, ENGLISH])]));
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+        loc.add_bundle_from_sources(langid!("fr"), &["test-key-a = Bonjour le monde"])
+            .unwrap();
+        loc.add_bundle_from_sources(JAPANESE, &["test-key-b = ジャパン"])
+            .unwrap();
+
+        let fr = langid!("fr");
+        assert_eq!(
+            loc.format_message(&fr, "test-key-a", None),
+            Some(String::from("Bonjour le monde"))
+        );
+        // Japanese is tried first, then English
+        assert_eq!(
+            loc.format_message(&fr, "test-key-b", None),
+            Some(String::from("ジャパン"))
+        );
+        let attribute = MessageAttribute {
+            key: "attribute-test",
+            attribute: "attribute_b",
+        };
+        assert_eq!(
+            loc.format_message(&fr, &attribute, None),
+            Some(String::from("there!"))
+        );
+        // The chain of the matched locale is used for regional variants
+        assert_eq!(
+            loc.format_message(&langid!("fr-CA"), &attribute, None),
+            Some(String::from("there!"))
+        );
+        assert!(matches!(
+            loc.format_message_result(&fr, "missing-key", None),
+            Err(LocalizerError::MessageNotFound { .. })
+        ));
+        // Locales without a chain do not fall back
+        assert!(loc.format_message(&JAPANESE, "test-key-a", None).is_none());
+    }
+
+    #[test]
+    fn can_disable_isolating_marks() {
+        let mut args = FluentArgs::new();
+        args.set("name", "Bob");
+
+        // Bundles loaded before and after the option is set are both affected
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+        let mut loc = loc.set_use_isolating(false);
+        loc.add_bundle(JAPANESE, &[MAIN]).unwrap();
+
+        assert!(!loc.use_isolating());
+        for locale in [ENGLISH, JAPANESE] {
+            assert_eq!(
+                loc.format_message(&locale, "test-name", Some(&args)),
+                Some(String::from("Peg Bob"))
+            );
+        }
+    }
+
+    #[test]
+    fn can_add_function_to_all_bundles() {
+        let mut loc = Localizer::new().set_use_isolating(false);
+        loc.add_bundle_from_sources(ENGLISH, &["shout = { SHOUT($name) }!"])
+            .unwrap();
+
+        loc.add_function("SHOUT", |positional, _named| match positional {
+            [FluentValue::String(s)] => FluentValue::from(s.to_uppercase()),
+            _ => FluentValue::Error,
+        })
+        .unwrap();
+
+        // Bundles added later also get the function
+        loc.add_bundle_from_sources(JAPANESE, &["shout = { SHOUT($name) }！"])
+            .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("name", "bob");
+
+        assert_eq!(
+            loc.format_message(&ENGLISH, "shout", Some(&args)),
+            Some(String::from("BOB!"))
+        );
+        assert_eq!(
+            loc.format_message(&JAPANESE, "shout", Some(&args)),
+            Some(String::from("BOB！"))
+        );
+        assert!(loc.add_function("SHOUT", |_, _| FluentValue::None).is_err());
+    }
+
+    #[test]
+    fn can_format_default_locale() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+
+        assert!(loc.format_default("test-key-a", None).is_none());
+
+        let loc = loc.set_default_locale(ENGLISH);
+
+        assert_eq!(loc.default_locale(), Some(&ENGLISH));
+        assert_eq!(
+            loc.format_default("test-key-a", None),
+            Some(String::from("Hello World"))
+        );
+    }
+
+    #[test]
+    fn missing_default_bundle_returns_none() {
+        let mut loc = Localizer::new().set_default_locale(JAPANESE);
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+
+        assert!(loc.format_default("test-key-a", None).is_none());
+    }
+
+    #[test]
+    fn can_load_from_map() {
+        let loc = Localizer::from_map(HashMap::from([
+            (ENGLISH, vec![PathBuf::from(MAIN), PathBuf::from(SUB)]),
+            (JAPANESE, vec![PathBuf::from(MAIN)]),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-key-a", None),
+            Some(String::from("Hello World"))
+        );
+        assert!(loc.get_locale(&JAPANESE).is_some());
+    }
+
+    #[test]
+    fn from_map_names_failing_locale() {
+        let err = Localizer::from_map(HashMap::from([
+            (ENGLISH, vec![PathBuf::from(MAIN)]),
+            (JAPANESE, vec![PathBuf::from("test_data/missing.ftl")]),
+        ]))
+        .unwrap_err();
+
+        assert!(matches!(
+            &err,
+            LocalizerError::Bundle { locale, source }
+                if *locale == JAPANESE && matches!(**source, LocalizerError::Io { .. })
+        ));
+        assert!(err.to_string().contains("locale ja"));
+    }
+
+    #[test]
+    fn can_extend_bundle() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+
+        loc.extend_bundle(ENGLISH, &["test_data/extend.ftl"])
+            .unwrap();
+
+        // Overridden, added and kept messages
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-key-a", None),
+            Some(String::from("Hello Plugin"))
+        );
+        assert_eq!(
+            loc.format_message(&ENGLISH, "plugin-key", None),
+            Some(String::from("Plugin message"))
+        );
+        assert!(loc
+            .format_message(&ENGLISH, "test-name", Some(&FluentArgs::new()))
+            .is_some());
+        assert_eq!(
+            loc.message_keys(&ENGLISH).unwrap(),
+            vec!["attribute-test", "plugin-key", "test-key-a", "test-name"]
+        );
+
+        // Creates the bundle if missing
+        loc.extend_bundle(JAPANESE, &["test_data/extend.ftl"])
+            .unwrap();
+        assert_eq!(
+            loc.format_message(&JAPANESE, "plugin-key", None),
+            Some(String::from("Plugin message"))
+        );
+    }
+
+    #[test]
+    fn can_format_terms() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &["test_data/terms.ftl"]).unwrap();
+
+        assert_eq!(
+            loc.format_message(&ENGLISH, "welcome", None),
+            Some(String::from("Welcome to Axum L10n"))
+        );
+        assert_eq!(
+            loc.format_message(&ENGLISH, "-brand-name", None),
+            Some(String::from("Axum L10n"))
+        );
+        assert!(matches!(
+            loc.format_message_result(&ENGLISH, "-missing", None),
+            Err(LocalizerError::MessageNotFound { .. })
+        ));
+        assert!(loc
+            .message_keys(&ENGLISH)
+            .unwrap()
+            .contains(&"-brand-name".to_string()));
+    }
+
+    #[test]
+    fn cow_format_borrows_static_messages() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        assert!(matches!(
+            loc.format_message_cow(&ENGLISH, "test-key-a", None),
+            Some(Cow::Borrowed("Hello World"))
+        ));
+
+        let mut args = FluentArgs::new();
+        args.set("name", "Bob");
+
+        // Interpolated messages are allocated
+        assert!(matches!(
+            loc.format_message_cow(&ENGLISH, "test-name", Some(&args)),
+            Some(Cow::Owned(message)) if message == "Peg \u{2068}Bob\u{2069}"
+        ));
+        assert!(loc.format_message_cow(&ENGLISH, "missing", None).is_none());
+    }
+
+    #[test]
+    fn can_apply_number_options() {
+        let mut loc = Localizer::new().set_fluent_number_options(FluentNumberOptions {
+            minimum_fraction_digits: Some(2),
+            ..Default::default()
+        });
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("number", 5);
+
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-key-b", Some(&args)),
+            Some(String::from("\u{2068}5\u{2069} new messages"))
+        );
+        assert_eq!(
+            loc.format_message_with_numbers(&ENGLISH, "test-key-b", Some(&args)),
+            Some(String::from("\u{2068}5.00\u{2069} new messages"))
+        );
+
+        // Numbers with their own options keep them
+        args.set(
+            "number",
+            FluentNumber::new(
+                5.0,
+                FluentNumberOptions {
+                    minimum_fraction_digits: Some(1),
+                    ..Default::default()
+                },
+            ),
+        );
+
+        assert_eq!(
+            loc.format_message_with_numbers(&ENGLISH, "test-key-b", Some(&args)),
+            Some(String::from("\u{2068}5.0\u{2069} new messages"))
+        );
+    }
+
+    #[cfg(feature = "displaynames")]
+    #[test]
+    fn can_find_display_names() {
+        let loc = Localizer::new();
+
+        assert_eq!(
+            loc.display_name(&JAPANESE, &ENGLISH).as_deref(),
+            Some("Japanese")
+        );
+        assert_eq!(
+            loc.display_name(&ENGLISH, &JAPANESE).as_deref(),
+            Some("英語")
+        );
+        assert_eq!(
+            loc.display_name(&langid!("en-GB"), &ENGLISH).as_deref(),
+            Some("British English")
+        );
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn can_format_datetime() {
+        let loc = Localizer::new();
+        let timestamp = chrono::DateTime::from_timestamp(1709649000, 0).unwrap();
+
+        assert_eq!(
+            loc.format_datetime(&ENGLISH, &timestamp, crate::DateTimeStyle::DateTime),
+            "3/5/2024 2:30 PM"
+        );
+        assert_eq!(
+            loc.format_datetime(
+                &langid!("fr-FR"),
+                &timestamp,
+                crate::DateTimeStyle::DateTime
+            ),
+            "05/03/2024 14:30"
+        );
+    }
+
+    #[test]
+    fn can_format_plural_messages() {
+        let mut loc = Localizer::new().set_use_isolating(false);
+        loc.add_bundle_from_sources(
+            ENGLISH,
+            &["emails = { $count ->
+    [one] { $name } has one email
+   *[other] { $name } has { $count } emails
+}"],
+        )
+        .unwrap();
+
+        let mut args = FluentArgs::new();
+        args.set("name", "Bob");
+
+        assert_eq!(
+            loc.format_plural(&ENGLISH, "emails", 1, Some(&args)),
+            Some(String::from("Bob has one email"))
+        );
+        assert_eq!(
+            loc.format_plural(&ENGLISH, "emails", 3_usize, Some(&args)),
+            Some(String::from("Bob has 3 emails"))
+        );
+
+        // The count uses the number options of the localizer
+        let loc = loc.set_fluent_number_options(FluentNumberOptions {
+            minimum_fraction_digits: Some(1),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            loc.format_plural(&ENGLISH, "emails", 2.5, Some(&args)),
+            Some(String::from("Bob has 2.5 emails"))
+        );
+        assert_eq!(
+            loc.format_plural(&ENGLISH, "emails", 1, Some(&args)),
+            Some(String::from("Bob has 1.0 emails"))
+        );
+    }
+
+    #[test]
+    fn can_find_plural_categories() {
+        let loc = Localizer::new();
+
+        assert_eq!(loc.plural_category(&ENGLISH, 1), PluralCategory::ONE);
+        assert_eq!(loc.plural_category(&ENGLISH, 2), PluralCategory::OTHER);
+        assert_eq!(
+            loc.plural_category(&langid!("en-US"), 1),
+            PluralCategory::ONE
+        );
+        assert_eq!(loc.plural_category(&JAPANESE, 1), PluralCategory::OTHER);
+
+        let polish = langid!("pl");
+        assert_eq!(loc.plural_category(&polish, 1), PluralCategory::ONE);
+        assert_eq!(loc.plural_category(&polish, 3), PluralCategory::FEW);
+        assert_eq!(loc.plural_category(&polish, 5), PluralCategory::MANY);
+        assert_eq!(loc.plural_category(&polish, 22), PluralCategory::FEW);
+
+        let arabic = langid!("ar-EG");
+        assert_eq!(loc.plural_category(&arabic, 0), PluralCategory::ZERO);
+        assert_eq!(loc.plural_category(&arabic, 2), PluralCategory::TWO);
+        assert_eq!(loc.plural_category(&arabic, 11), PluralCategory::MANY);
+
+        assert_eq!(
+            loc.plural_category(&ENGLISH, f64::NAN),
+            PluralCategory::OTHER
+        );
+
+        let loc = loc.set_fluent_number_options(FluentNumberOptions {
+            minimum_fraction_digits: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(loc.plural_category(&ENGLISH, 1), PluralCategory::OTHER);
+    }
+
+    #[cfg(feature = "zip")]
+    fn write_zip(name: &str, entries: &[(&str, &str)]) -> PathBuf {
+        use std::io::Write;
+
+        let path =
+            std::env::temp_dir().join(format!("axum_l10n_{name}_{}.zip", std::process::id()));
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+
+        for (entry, ftl) in entries {
+            writer
+                .start_file(*entry, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(ftl.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        path
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn can_add_bundle_from_zip() {
+        let path = write_zip(
+            "bundle",
+            &[
+                (
+                    "ja/main.ftl",
+                    "test-key-a = こんにちは\ntest-key-b = 上書き前",
+                ),
+                ("ja/sub.ftl", "test-key-b = 上書き後"),
+                ("en/main.ftl", "test-key-a = Hello World"),
+                ("ja/README.md", "not ftl"),
+            ],
+        );
+
+        let mut loc = Localizer::new();
+        loc.add_bundle_from_zip(JAPANESE, &path, "ja/*.ftl")
+            .unwrap();
+
+        assert_eq!(
+            loc.format_message(&JAPANESE, "test-key-a", None),
+            Some(String::from("こんにちは"))
+        );
+        assert_eq!(
+            loc.format_message(&JAPANESE, "test-key-b", None),
+            Some(String::from("上書き後"))
+        );
+        assert!(loc.format_message(&ENGLISH, "test-key-a", None).is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_errors_name_entry() {
+        let path = write_zip(
+            "invalid",
+            &[("ja/main.ftl", "valid = Valid\nbroken = { @ }")],
+        );
+
+        let err = Localizer::new()
+            .add_bundle_from_zip(JAPANESE, &path, "*.ftl")
+            .unwrap_err();
+
+        assert!(
+            matches!(&err, LocalizerError::Parse { path: entry, .. } if entry == &path.join("ja/main.ftl")),
+            "{err}"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+
+        let err = Localizer::new()
+            .add_bundle_from_zip(JAPANESE, "test_data/main.ftl", "*.ftl")
+            .unwrap_err();
+
+        assert!(matches!(err, LocalizerError::Zip { .. }), "{err}");
+    }
+
+    #[test]
+    fn unmatched_locales_use_fallback_bundle() {
+        let mut loc = Localizer::new().set_default_locale(ENGLISH);
+        loc.add_bundle_from_sources(JAPANESE, &["test-key-a = こんにちは"])
+            .unwrap();
+
+        assert!(loc.get_locale(&langid!("fr")).is_none());
+
+        loc.add_fallback_bundle(&[MAIN, "test_data/terms.ftl"])
+            .unwrap();
+
+        assert!(loc.get_locale(&langid!("fr")).is_some());
+        assert!(!loc.has_locale(&langid!("fr")));
+        assert_eq!(loc.available_locales(), vec![JAPANESE]);
+        assert_eq!(
+            loc.format_message(&langid!("fr"), "test-key-a", None),
+            Some(String::from("Hello World"))
+        );
+        assert_eq!(
+            loc.format_message_result(&langid!("de-DE"), "test-key-a", None)
+                .unwrap(),
+            "Hello World"
+        );
+        // Registered bundles are preferred, and fall back for their missing messages
+        assert_eq!(
+            loc.format_message(&JAPANESE, "test-key-a", None),
+            Some(String::from("こんにちは"))
+        );
+        assert!(loc.format_message(&JAPANESE, "test-name", None).is_some());
+        assert_eq!(
+            loc.format_message(&langid!("fr"), "-brand-name", None),
+            Some(String::from("Axum L10n"))
+        );
+        assert!(loc
+            .format_message(&langid!("fr"), "missing-key", None)
+            .is_none());
+    }
+
+    #[test]
+    fn resolve_locale_returns_matched_locale() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+        loc.add_bundle(langid!("en-US"), &[MAIN]).unwrap();
+
+        let (matched, _) = loc.resolve_locale(&langid!("en-GB")).unwrap();
+        assert_eq!(matched, &ENGLISH);
+
+        let (matched, _) = loc.resolve_locale(&langid!("en-US")).unwrap();
+        assert_eq!(matched, &langid!("en-US"));
+
+        assert!(loc.resolve_locale(&JAPANESE).is_none());
+
+        loc.add_fallback_bundle(&[MAIN]).unwrap();
+        let (matched, _) = loc.resolve_locale(&JAPANESE).unwrap();
+        assert_eq!(matched, &LanguageIdentifier::default());
+    }
+
+    #[test]
+    fn partial_matches_prefer_script_then_region() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("zh-Hant"), &[MAIN]).unwrap();
+        loc.add_bundle(langid!("zh-Hans"), &[MAIN]).unwrap();
+
+        // The first range of `zh-Hant-TW, zh-Hant;q=0.9, zh;q=0.5`
+        let (matched, _) = loc.resolve_locale(&langid!("zh-Hant-TW")).unwrap();
+        assert_eq!(matched, &langid!("zh-Hant"));
+
+        let (matched, _) = loc.resolve_locale(&langid!("zh-Hans-CN")).unwrap();
+        assert_eq!(matched, &langid!("zh-Hans"));
+
+        loc.add_bundle(langid!("zh-TW"), &[MAIN]).unwrap();
+        loc.add_bundle(langid!("zh-Hant-HK"), &[MAIN]).unwrap();
+        let (matched, _) = loc.resolve_locale(&langid!("zh-Hant-TW")).unwrap();
+        assert_eq!(matched, &langid!("zh-Hant"));
+
+        let (matched, _) = loc.resolve_locale(&langid!("zh-Hant-MO")).unwrap();
+        assert_eq!(matched, &langid!("zh-Hant"));
+
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("zh-Hans-CN"), &[MAIN]).unwrap();
+        loc.add_bundle(langid!("zh-Hant-HK"), &[MAIN]).unwrap();
+
+        // The script is kept over the sorted order of the language variants
+        let (matched, _) = loc.resolve_locale(&langid!("zh-Hant-TW")).unwrap();
+        assert_eq!(matched, &langid!("zh-Hant-HK"));
+    }
+
+    #[test]
+    fn can_get_message_source() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN]).unwrap();
+
+        assert_eq!(
+            loc.get_message_source(&ENGLISH, "test-name").as_deref(),
+            Some("test-name = Peg { $name }")
+        );
+        assert_eq!(
+            loc.get_message_source(&langid!("en-GB"), "attribute-test")
+                .as_deref(),
+            Some("attribute-test =\n    .attribute_a = Hello\n    .attribute_b = there!")
+        );
+        assert_eq!(
+            loc.get_message_source(
+                &ENGLISH,
+                &MessageAttribute {
+                    key: "attribute-test",
+                    attribute: "attribute_b"
+                }
+            )
+            .as_deref(),
+            Some(".attribute_b = there!")
+        );
+
+        let mut args = FluentArgs::new();
+        args.set("name", "Sue");
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-name", Some(&args)),
+            Some(String::from("Peg \u{2068}Sue\u{2069}"))
+        );
+
+        assert!(loc.get_message_source(&ENGLISH, "missing-key").is_none());
+        assert!(loc.get_message_source(&JAPANESE, "test-name").is_none());
+    }
+
+    #[test]
+    fn can_list_language_options() {
+        let mut loc = Localizer::new();
+        loc.add_bundle_from_sources(ENGLISH, &["-language-name = English"])
+            .unwrap();
+        loc.add_bundle_from_sources(JAPANESE, &["-language-name = 日本語"])
+            .unwrap();
+        loc.add_bundle(langid!("fr"), &[MAIN]).unwrap();
+
+        let options = loc.language_switcher(&langid!("ja-JP"));
+
+        assert_eq!(
+            options,
+            vec![
+                LanguageOption {
+                    locale: ENGLISH,
+                    name: String::from("English"),
+                    current: false,
+                },
+                LanguageOption {
+                    locale: langid!("fr"),
+                    name: String::from("fr"),
+                    current: false,
+                },
+                LanguageOption {
+                    locale: JAPANESE,
+                    name: String::from("日本語"),
+                    current: true,
+                },
+            ]
+        );
+        assert!(loc
+            .language_switcher(&langid!("de"))
+            .iter()
+            .all(|option| !option.current));
+    }
+
+    #[test]
+    fn can_reload_bundles() {
+        let dir = std::env::temp_dir().join(format!("axum_l10n_reload_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ftl_path = dir.join("main.ftl");
+        std::fs::write(&ftl_path, "test-key-a = Hello World").unwrap();
+
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[&ftl_path]).unwrap();
+        loc.add_bundle_from_sources(JAPANESE, &["test-key-a = こんにちは"])
+            .unwrap();
+
+        std::fs::write(&ftl_path, "test-key-a = Hello Again\nnew-key = New").unwrap();
+        loc.reload().unwrap();
+
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-key-a", None),
+            Some(String::from("Hello Again"))
+        );
+        assert_eq!(
+            loc.format_message(&ENGLISH, "new-key", None),
+            Some(String::from("New"))
+        );
+        assert_eq!(
+            loc.format_message(&JAPANESE, "test-key-a", None),
+            Some(String::from("こんにちは"))
+        );
+
+        // A failed reload keeps the previous bundles
+        std::fs::write(&ftl_path, "test-key-a = { @ }").unwrap();
+        let err = loc.reload().unwrap_err();
+
+        assert!(matches!(err, LocalizerError::Bundle { locale, .. } if locale == ENGLISH));
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-key-a", None),
+            Some(String::from("Hello Again"))
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn can_format_several_messages() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(ENGLISH, &[MAIN, SUB]).unwrap();
+
+        let messages = loc.format_messages(
+            &langid!("en-US"),
+            &["test-key-a", "test-key-b", "does-not-exist"],
+        );
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages["test-key-a"].as_deref(), Some("Hello World"));
+        // Missing arguments are replaced by Fluent, as in format_message
+        assert_eq!(
+            messages["test-key-b"].as_deref(),
+            Some("\u{2068}{$number}\u{2069} new messages")
+        );
+        assert_eq!(messages["does-not-exist"], None);
+        assert!(loc
+            .format_messages(&JAPANESE, &["test-key-a"])
+            .values()
+            .all(Option::is_none));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn can_add_bundle_async() {
+        let mut loc = Localizer::new();
+        loc.add_bundle_async(ENGLISH, &[MAIN, SUB]).await.unwrap();
+
+        assert_eq!(
+            loc.format_message(&ENGLISH, "test-key-a", None),
+            Some(String::from("Hello World"))
+        );
+        assert_eq!(
+            loc.message_keys(&ENGLISH).unwrap(),
+            vec!["attribute-test", "test-key-a", "test-key-b", "test-name"]
+        );
+
+        // The bundle is replaced as with add_bundle
+        loc.add_bundle_async(ENGLISH, &[SUB]).await.unwrap();
+        assert!(loc.format_message(&ENGLISH, "test-key-a", None).is_none());
+
+        assert!(matches!(
+            loc.add_bundle_async(ENGLISH, &["test_data/missing.ftl"])
+                .await,
+            Err(LocalizerError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn can_fall_back_to_equivalent_languages() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("no"), &[MAIN]).unwrap();
+        loc.add_bundle(langid!("fil"), &[SUB]).unwrap();
+
+        // Opt-in
+        assert!(loc.get_locale(&langid!("nb")).is_none());
+
+        let loc = loc.set_macrolanguage_fallback(true);
+
+        assert_eq!(
+            loc.format_message(&langid!("nb-NO"), "test-key-a", None),
+            Some(String::from("Hello World"))
+        );
+        assert_eq!(
+            loc.message_keys(&langid!("nn")),
+            loc.message_keys(&langid!("no"))
+        );
+        assert_eq!(
+            loc.message_keys(&langid!("tl")).unwrap(),
+            vec!["test-key-b"]
+        );
+        assert!(loc.get_locale(&langid!("da")).is_none());
+
+        let mut loc = Localizer::new().set_macrolanguage_fallback(true);
+        loc.add_bundle(langid!("nb"), &[MAIN]).unwrap();
+
+        assert!(loc.get_locale(&langid!("no")).is_some());
+        assert!(loc.get_locale(&langid!("nn")).is_none());
+    }
+
+    #[test]
+    fn can_check_available_locales() {
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("en-US"), &[MAIN]).unwrap();
+        loc.add_bundle(JAPANESE, &[MAIN]).unwrap();
+
+        assert!(loc.has_locale(&langid!("en-US")));
+        assert!(loc.has_locale(&JAPANESE));
+        // Language only matches
+        assert!(loc.has_locale(&ENGLISH));
+        assert!(loc.has_locale(&langid!("ja-JP")));
+        assert!(!loc.has_locale(&langid!("fr")));
+
+        assert_eq!(loc.available_locales(), vec![langid!("en-US"), JAPANESE]);
+        assert!(Localizer::new().available_locales().is_empty());
+    }
+
+    #[test]
+    fn lenient_bundle_keeps_valid_entries() {
+        const JUNK: &str = "test_data/invalid/junk.ftl";
+        let mut loc = Localizer::new();
+
+        assert!(matches!(
+            loc.add_bundle(ENGLISH, &[MAIN, JUNK]),
+            Err(LocalizerError::Parse { .. })
+        ));
+
+        let warnings = loc.add_bundle_lenient(ENGLISH, &[MAIN, JUNK]).unwrap();
+
+        assert!(matches!(
+            warnings.as_slice(),
+            [LocalizerError::Parse { path, errors, .. }] if path == Path::new(JUNK) && errors.len() == 1
+        ));
+        assert_eq!(
+            loc.format_message(&ENGLISH, "valid-a", None),
+            Some(String::from("Valid message"))
+        );
+        assert_eq!(
+            loc.format_message(&ENGLISH, "valid-b", None),
+            Some(String::from("Another valid message"))
+        );
+        assert!(loc.format_message(&ENGLISH, "test-key-a", None).is_some());
+    }
+
+    #[test]
+    fn parse_errors_name_line_and_column() {
+        let mut loc = Localizer::new();
+
+        let err = loc
+            .add_bundle_from_sources(ENGLISH, &["valid = Valid\nbroken = { @ }\nok = Ok\n"])
+            .unwrap_err();
+        let message = err.to_string();
+
+        assert!(
+            message.starts_with("Localizer error: failed to parse FTL source at index 0"),
+            "{message}"
+        );
+        assert!(message.contains("line 2, column"), "{message}");
+        assert!(message.contains("| broken = { @ }"), "{message}");
+
+        let err = loc
+            .add_bundle(ENGLISH, &["test_data/invalid/junk.ftl"])
+            .unwrap_err();
+
+        assert!(err.to_string().contains("line 2, column 6"), "{err}");
+    }
 }
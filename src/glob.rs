@@ -0,0 +1,69 @@
+/// Matches a path against a glob pattern.
+///
+/// - `*` matches any characters except `/`
+/// - `**` matches any characters including `/`
+/// - `?` matches a single character except `/`
+///
+/// Patterns that do not start with `/` are matched against the last segment of the path,
+/// so `*.css` matches `/assets/main.css`.
+pub(crate) fn glob_matches(pattern: &str, path: &str) -> bool {
+    if pattern.starts_with('/') {
+        matches(pattern.as_bytes(), path.as_bytes())
+    } else {
+        let last_segment = path.rsplit('/').next().unwrap_or(path);
+
+        matches(pattern.as_bytes(), last_segment.as_bytes())
+    }
+}
+
+fn matches(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+        [b'*', rest @ ..] => {
+            let segment_len = path.iter().position(|c| *c == b'/').unwrap_or(path.len());
+
+            (0..=segment_len).any(|i| matches(rest, &path[i..]))
+        }
+        [b'?', rest @ ..] => match path {
+            [c, path_rest @ ..] if *c != b'/' => matches(rest, path_rest),
+            _ => false,
+        },
+        [c, rest @ ..] => match path {
+            [path_c, path_rest @ ..] if path_c == c => matches(rest, path_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_exact_path() {
+        assert!(glob_matches("/health", "/health"));
+        assert!(!glob_matches("/health", "/healthz"));
+    }
+
+    #[test]
+    fn double_star_matches_nested_paths() {
+        assert!(glob_matches("/assets/**", "/assets/css/main.css"));
+        assert!(glob_matches("/assets/**", "/assets/"));
+        assert!(!glob_matches("/assets/**", "/assetsxyz/main.css"));
+    }
+
+    #[test]
+    fn single_star_stays_in_segment() {
+        assert!(glob_matches("/api/*/users", "/api/v1/users"));
+        assert!(!glob_matches("/api/*/users", "/api/v1/beta/users"));
+        assert!(glob_matches("/file-?.json", "/file-a.json"));
+    }
+
+    #[test]
+    fn relative_pattern_matches_last_segment() {
+        assert!(glob_matches("*.css", "/assets/css/main.css"));
+        assert!(glob_matches("*.json", "/data.json"));
+        assert!(!glob_matches("*.css", "/assets/main.css/page"));
+    }
+}
@@ -0,0 +1,252 @@
+use std::borrow::Cow;
+
+use fluent::{
+    types::{FluentNumber, FluentNumberOptions},
+    FluentArgs, FluentValue,
+};
+use serde_json::Value;
+
+use crate::LocalizerError;
+
+/// Builds Fluent arguments from JSON values, for example a `serde_json::Map` deserialized
+/// from a request body.
+///
+/// Entries whose key is in `reserved` are skipped.
+/// Numbers are converted using the given number options, and arrays are joined with the
+/// list separator. Objects cannot be converted and return an error.
+pub fn fluent_args_from_json<'a, I>(
+    values: I,
+    number_opts: &FluentNumberOptions,
+    list_separator: &str,
+    reserved: &[&str],
+) -> Result<FluentArgs<'a>, LocalizerError>
+where
+    I: IntoIterator<Item = (&'a String, &'a Value)>,
+{
+    values
+        .into_iter()
+        .filter(|(key, _)| !reserved.contains(&key.as_str()))
+        .map(|(key, val)| {
+            json_value_to_fluent_value(val, number_opts, list_separator)
+                .map(|val| (key.as_str(), val))
+                .ok_or_else(|| LocalizerError::InvalidArgument {
+                    key: key.to_string(),
+                    reason: String::from("objects cannot be used as fluent arguments"),
+                })
+        })
+        .collect()
+}
+
+/// The keys of the options of Fluent's `NUMBER` builtin, Ex. `minimumFractionDigits`
+pub(crate) const NUMBER_OPTION_KEYS: &[&str] = &[
+    "style",
+    "currency",
+    "currencyDisplay",
+    "useGrouping",
+    "minimumIntegerDigits",
+    "minimumFractionDigits",
+    "maximumFractionDigits",
+    "minimumSignificantDigits",
+    "maximumSignificantDigits",
+];
+
+/// Merges the number options found in JSON values into a copy of the given options, Ex.
+/// `style="currency"` and `minimumFractionDigits=2`, with the same keys as Fluent's `NUMBER`
+/// builtin.
+///
+/// Other keys are ignored.
+///
+/// # Example
+/// ```ignore
+/// let number_opts = axum_l10n::number_options_from_json(&json_map, localizer.number_options());
+/// let args =
+///     axum_l10n::fluent_args_from_json(&json_map, &number_opts, localizer.list_separator(), &[])
+///         .unwrap();
+/// ```
+pub fn number_options_from_json<'a, I>(
+    values: I,
+    number_opts: &FluentNumberOptions,
+) -> FluentNumberOptions
+where
+    I: IntoIterator<Item = (&'a String, &'a Value)>,
+{
+    let options = values
+        .into_iter()
+        .filter(|(key, _)| NUMBER_OPTION_KEYS.contains(&key.as_str()))
+        .filter_map(|(key, val)| {
+            json_value_to_fluent_value(val, number_opts, "").map(|val| (key.as_str(), val))
+        })
+        .collect::<FluentArgs>();
+
+    let mut number_opts = number_opts.clone();
+    number_opts.merge(&options);
+
+    number_opts
+}
+
+/// Converts a JSON value to a fluent value, returning None for objects
+pub(crate) fn json_value_to_fluent_value<'a>(
+    json_value: &'a serde_json::Value,
+    number_opts: &FluentNumberOptions,
+    list_separator: &str,
+) -> Option<fluent::FluentValue<'a>> {
+    let value = match json_value {
+        Value::Number(n) => n
+            .as_f64()
+            .map(|n_f64| {
+                let f_n = FluentNumber::new(n_f64, number_opts.clone());
+                FluentValue::Number(f_n)
+            })
+            .unwrap_or_else(|| FluentValue::from(n.to_string())),
+        Value::String(s) => FluentValue::String(Cow::Borrowed(s)),
+        Value::Null => FluentValue::None,
+        Value::Array(values) => {
+            let values = values
+                .iter()
+                .map(|val| {
+                    json_value_to_fluent_value(val, number_opts, list_separator)
+                        .map(|val| fluent_value_to_string(&val))
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            FluentValue::from(values.join(list_separator))
+        }
+        // Fluent has no boolean type, so booleans are passed as strings for use in selectors
+        Value::Bool(true) => FluentValue::String(Cow::Borrowed("true")),
+        Value::Bool(false) => FluentValue::String(Cow::Borrowed("false")),
+        Value::Object(_) => return None,
+    };
+
+    Some(value)
+}
+
+fn fluent_value_to_string(value: &FluentValue) -> String {
+    match value {
+        FluentValue::String(s) => s.to_string(),
+        FluentValue::Number(n) => n.as_string().to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent::types::FluentNumberStyle;
+
+    use super::*;
+
+    #[test]
+    fn can_convert_num_to_fluent_num() {
+        let num = serde_json::Value::from(2);
+
+        let fluent_num = json_value_to_fluent_value(&num, &FluentNumberOptions::default(), ", ");
+
+        assert_eq!(
+            fluent_num,
+            Some(FluentValue::from(FluentNumber::new(
+                2_f64,
+                FluentNumberOptions::default()
+            )))
+        );
+    }
+
+    #[test]
+    fn can_build_args_from_json_map() {
+        let json = serde_json::json!({
+            "key": "test-name",
+            "name": "Deadpool",
+            "count": 3,
+            "missing": null,
+        });
+        let map = json.as_object().unwrap();
+
+        let args =
+            fluent_args_from_json(map, &FluentNumberOptions::default(), ", ", &["key"]).unwrap();
+
+        assert!(args.get("key").is_none());
+        assert_eq!(args.get("name"), Some(&FluentValue::from("Deadpool")));
+        assert_eq!(
+            args.get("count"),
+            Some(&FluentValue::from(FluentNumber::new(
+                3_f64,
+                FluentNumberOptions::default()
+            )))
+        );
+        assert!(matches!(args.get("missing"), Some(FluentValue::None)));
+    }
+
+    #[test]
+    fn can_convert_bool_to_fluent_string() {
+        let opts = FluentNumberOptions::default();
+
+        assert_eq!(
+            json_value_to_fluent_value(&Value::Bool(true), &opts, ", "),
+            Some(FluentValue::from("true"))
+        );
+        assert_eq!(
+            json_value_to_fluent_value(&Value::Bool(false), &opts, ", "),
+            Some(FluentValue::from("false"))
+        );
+    }
+
+    #[test]
+    fn can_join_array_of_strings() {
+        let list = serde_json::json!(["a", "b", "c"]);
+
+        let value = json_value_to_fluent_value(&list, &FluentNumberOptions::default(), ", ");
+
+        assert_eq!(value, Some(FluentValue::from("a, b, c")));
+    }
+
+    #[test]
+    fn can_join_array_of_numbers() {
+        let list = serde_json::json!([1, 2.5, 3]);
+        let opts = FluentNumberOptions {
+            minimum_fraction_digits: Some(1),
+            ..Default::default()
+        };
+
+        let value = json_value_to_fluent_value(&list, &opts, " / ");
+
+        assert_eq!(value, Some(FluentValue::from("1.0 / 2.5 / 3.0")));
+    }
+
+    #[test]
+    fn can_read_number_options() {
+        let json = serde_json::json!({
+            "amount": 5,
+            "style": "currency",
+            "currency": "USD",
+            "minimumFractionDigits": 2,
+            "useGrouping": false,
+        });
+
+        let opts = number_options_from_json(
+            json.as_object().unwrap(),
+            &FluentNumberOptions {
+                maximum_fraction_digits: Some(3),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(opts.style, FluentNumberStyle::Currency);
+        assert_eq!(opts.currency.as_deref(), Some("USD"));
+        assert_eq!(opts.minimum_fraction_digits, Some(2));
+        assert_eq!(opts.maximum_fraction_digits, Some(3));
+        assert!(!opts.use_grouping);
+    }
+
+    #[test]
+    fn objects_are_rejected() {
+        let json = serde_json::json!({ "user": { "name": "Bob" } });
+
+        let err = fluent_args_from_json(
+            json.as_object().unwrap(),
+            &FluentNumberOptions::default(),
+            ", ",
+            &[],
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, LocalizerError::InvalidArgument { key, .. } if key == "user"));
+    }
+}
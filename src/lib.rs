@@ -1,16 +1,67 @@
-use std::{future::Future, pin::Pin};
-
-use http::{HeaderMap, Response, StatusCode, Uri};
+use std::{borrow::Cow, future::Future, pin::Pin, sync::Arc};
+
+use cache::HeaderCache;
+use http::{
+    header::CONTENT_LANGUAGE, request::Parts, uri::PathAndQuery, HeaderMap, HeaderValue, Method,
+    Response, StatusCode, Uri,
+};
+use supported::SupportedLanguages;
 use tower::{Layer, Service};
 use unic_langid::LanguageIdentifier;
 
+mod cache;
+#[cfg(feature = "datetime")]
+mod datetime;
+mod direction;
+#[cfg(feature = "displaynames")]
+mod displaynames;
+mod error;
+mod experiment;
+mod extract;
+mod glob;
+mod supported;
+#[cfg(feature = "datetime")]
+pub use datetime::DateTimeStyle;
+pub use direction::{text_direction, Direction};
+pub use error::{ExtractionSource, LanguageIdentifierExtractorError};
+pub use experiment::{LocaleExperiment, SeedSource};
+#[cfg(feature = "fluent")]
+pub use extract::Translator;
+pub use extract::{Locale, LocaleRejection, LocalizedUri, ResolvedLocale};
+
 #[cfg(feature = "fluent")]
 mod fluent;
 #[cfg(feature = "fluent")]
-pub use fluent::Localizer;
+pub use fluent::{LanguageOption, Localizer, LocalizerError, MessageAttribute, MessageKey};
+#[cfg(feature = "fluent")]
+pub use intl_pluralrules::PluralCategory;
+
+#[cfg(feature = "fluent")]
+mod serialize;
+
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub use json::{fluent_args_from_json, number_options_from_json};
 
 #[cfg(feature = "tera")]
 mod tera;
+#[cfg(feature = "tera")]
+pub use tera::{
+    BoundLocalizer, HreflangAlternates, LocaleDirection, LocalizedPath, LocalizerTeraConfig,
+    MissingKey, SharedLocalizer,
+};
+
+#[cfg(all(feature = "tera", feature = "datetime"))]
+pub use tera::FormatDatetime;
+
+#[cfg(feature = "negotiation")]
+pub use fluent_langneg::NegotiationStrategy;
+
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "watch")]
+pub use watch::WatchedLocalizer;
 
 /// The redirect mode for the service.
 #[derive(Debug, Clone)]
@@ -23,30 +74,353 @@ pub enum RedirectMode {
     /// Redirects to sub-path (/<lang>/*) if in list of supported Languages
     /// Ex. localhost:3000/lists -> localhost:3000/en/lists
     RedirectToLanguageSubPath,
+    /// Serves the default language at the bare path and other languages at a sub-path (/<lang>/*)
+    /// Ex. localhost:3000/about is in the default language and localhost:3000/ja/about is in
+    /// Japanese. Paths without a language are not redirected, unless another language is
+    /// requested with the locale query parameter.
+    PrefixNonDefaultOnly,
+}
+
+impl RedirectMode {
+    /// The language code used in the path, None if the mode does not use sub-paths
+    fn path_lang_code(&self, ident: &LanguageIdentifier) -> Option<String> {
+        match self {
+            RedirectMode::RedirectToFullLocaleSubPath => Some(ident.to_string()),
+            RedirectMode::RedirectToLanguageSubPath | RedirectMode::PrefixNonDefaultOnly => {
+                Some(ident.language.to_string())
+            }
+            RedirectMode::NoRedirect => None,
+        }
+    }
+}
+
+/// Prefixes a path with the language sub-path of the redirect mode, Ex. `/products/42` to
+/// `/ja/products/42`, to build links to a locale from handlers.
+///
+/// The path may include a query string. It is returned unchanged with `RedirectMode::NoRedirect`.
+/// With `RedirectMode::PrefixNonDefaultOnly` every language is prefixed, as the default language
+/// is not known, use `localized_path` of the layer or service instead.
+///
+/// # Example
+/// ```ignore
+/// let path = axum_l10n::localized_path(
+///     &langid!("ja"),
+///     "/products/42?page=2",
+///     &axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+/// );
+///
+/// assert_eq!(path, "/ja/products/42?page=2");
+/// ```
+pub fn localized_path(locale: &LanguageIdentifier, path: &str, mode: &RedirectMode) -> String {
+    prefixed_path(locale, path, mode, None)
+}
+
+/// Prefixes a path like [localized_path], leaving the default language unprefixed with
+/// `RedirectMode::PrefixNonDefaultOnly`
+pub(crate) fn prefixed_path(
+    locale: &LanguageIdentifier,
+    path: &str,
+    mode: &RedirectMode,
+    default_lang: Option<&LanguageIdentifier>,
+) -> String {
+    if matches!(mode, RedirectMode::PrefixNonDefaultOnly)
+        && default_lang.is_some_and(|default_lang| default_lang.language == locale.language)
+    {
+        return match path {
+            "" => String::from("/"),
+            path => path.to_string(),
+        };
+    }
+
+    let Some(lang_code) = mode.path_lang_code(locale) else {
+        return path.to_string();
+    };
+
+    let separator = if path.is_empty() || path.starts_with(['/', '?']) {
+        ""
+    } else {
+        "/"
+    };
+
+    format!("/{lang_code}{separator}{path}")
+}
+
+/// How a requested language identifier is matched against the supported languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrictness {
+    /// Only compares the language subtag, Ex. `zh-Hant` matches `zh-Hans`
+    #[default]
+    Language,
+    /// Compares the language and script subtags, Ex. `zh-Hant` matches `zh-Hant-TW` but not
+    /// `zh-Hans`
+    /// Requests without a script, Ex. `zh`, match any script of the language.
+    LanguageScript,
+    /// Compares the full locale, Ex. `zh-Hant-TW` only matches `zh-Hant-TW`
+    FullLocale,
+}
+
+/// Errors from configuring a [LanguageIdentifierExtractorLayer].
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A language tag could not be parsed
+    InvalidLanguageTag {
+        tag: String,
+        source: unic_langid::LanguageIdentifierError,
+    },
+    /// The default language is not one of the supported languages
+    UnsupportedDefaultLanguage(LanguageIdentifier),
+    /// An excluded path does not start with `/`
+    InvalidExcludedPath(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidLanguageTag { tag, source } => {
+                write!(f, "Invalid language tag '{}': {}", tag, source)
+            }
+            ConfigError::UnsupportedDefaultLanguage(lang) => write!(
+                f,
+                "Default language '{}' is not in the supported languages",
+                lang
+            ),
+            ConfigError::InvalidExcludedPath(path) => {
+                write!(f, "Excluded path '{}' does not start with '/'", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::InvalidLanguageTag { source, .. } => Some(source),
+            ConfigError::UnsupportedDefaultLanguage(_) | ConfigError::InvalidExcludedPath(_) => {
+                None
+            }
+        }
+    }
+}
+
+fn parse_language_tag(tag: &str) -> Result<LanguageIdentifier, ConfigError> {
+    tag.parse::<LanguageIdentifier>()
+        .map_err(|source| ConfigError::InvalidLanguageTag {
+            tag: tag.to_string(),
+            source,
+        })
+}
+
+/// Prepends `/` to paths configured without it, Ex. `.well-known`
+fn with_leading_slash(path: &str) -> String {
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{path}")
+    }
+}
+
+/// The body of redirect responses, see `redirect_body`
+#[derive(Debug, Clone)]
+struct RedirectBody {
+    template: String,
+    content_type: HeaderValue,
+}
+
+impl RedirectBody {
+    /// Interpolates the redirect target, escaped for HTML bodies
+    fn render(&self, location: &str) -> String {
+        let is_html = self
+            .content_type
+            .to_str()
+            .is_ok_and(|content_type| content_type.starts_with("text/html"));
+        let location = if is_html {
+            Cow::Owned(escape_html(location))
+        } else {
+            Cow::Borrowed(location)
+        };
+
+        self.template.replace("{url}", &location)
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+type ResolverFn = dyn Fn(&Parts) -> Option<LanguageIdentifier> + Send + Sync;
+
+/// How trailing slashes of the path are handled when redirecting to a localized path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+    /// Keeps the path as requested, Ex. `/lists/` -> `/en/lists/`
+    #[default]
+    Preserve,
+    /// Removes trailing slashes, Ex. `/lists/` -> `/en/lists` and `/` -> `/en`
+    Strip,
+    /// Ends the path with a slash, Ex. `/lists` -> `/en/lists/`
+    Add,
+}
+
+/// Where the language identifier of a request was found.
+///
+/// Inserted into the request extensions with the language identifier, Ex. to tell an explicit
+/// choice in the URL from a guess from the Accept-Language header.
+///
+/// # Example
+/// ```ignore
+/// async fn handler(Locale(lang): Locale, source: LocaleSource) -> String {
+///     match source {
+///         LocaleSource::Header | LocaleSource::Default => format!("We guessed {lang}"),
+///         _ => format!("You chose {lang}"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocaleSource {
+    /// The first segment of the path
+    Uri,
+    /// The locale query parameter
+    Query,
+    /// The locale cookie
+    Cookie,
+    /// The custom resolver
+    Resolver,
+    /// The Accept-Language header, or the language parameter of the Accept header
+    Header,
+    /// The default language
+    Default,
+    /// A language identifier inserted into the request extensions by an upstream layer, Ex.
+    /// from an auth token
+    Upstream,
+}
+
+/// Receives the decisions of the service, for example to count redirects with a metrics
+/// library.
+///
+/// # Example
+/// ```ignore
+/// struct RedirectCounter(prometheus::IntCounter);
+///
+/// impl axum_l10n::L10nObserver for RedirectCounter {
+///     fn on_redirect(&self, _from: &str, _to: &str) {
+///         self.0.inc();
+///     }
+/// }
+/// ```
+pub trait L10nObserver: Send + Sync {
+    /// Called with the original path and query, and the `Location` of each redirect
+    fn on_redirect(&self, from: &str, to: &str) {
+        let _ = (from, to);
+    }
+
+    /// Called for each request given a language identifier, including redirects
+    fn on_resolved(&self, locale: &LanguageIdentifier, source: LocaleSource) {
+        let _ = (locale, source);
+    }
+}
+
+#[derive(Clone)]
+struct Observer(Arc<dyn L10nObserver>);
+
+impl std::fmt::Debug for Observer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+/// A custom function resolving the language identifier of a request
+#[derive(Clone)]
+struct Resolver(Arc<ResolverFn>);
+
+impl std::fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Resolver")
+    }
+}
+
+/// Where the `Location` header of a redirect points to.
+#[derive(Debug, Clone)]
+enum RedirectBase {
+    /// A path relative to the current origin, ex. `/en/lists`
+    Relative,
+    /// An absolute URL using the `Host` header of the request
+    Host,
+    /// An absolute URL using a configured base, ex. `https://example.com`
+    Url(String),
 }
 
 #[derive(Debug, Clone)]
 pub struct LanguageIdentifierExtractor<S> {
     inner: S,
     default_lang: LanguageIdentifier,
-    supported_langs: Vec<LanguageIdentifier>,
+    supported_langs: SupportedLanguages,
     redirect_mode: RedirectMode,
+    included_paths: Vec<String>,
     excluded_paths: Vec<String>,
+    excluded_path_methods: Vec<(String, Method)>,
+    reserved_segments: Vec<String>,
+    path_aliases: Vec<(String, LanguageIdentifier)>,
+    excluded_globs: Vec<String>,
     redirect_default_as_301: bool,
+    redirect_all_methods: bool,
+    collapse_full_locale_redirect: bool,
+    content_language: bool,
+    redirect_base: RedirectBase,
+    redirect_body: Option<RedirectBody>,
+    match_strictness: MatchStrictness,
+    header_cache: Option<HeaderCache>,
+    resolver: Option<Resolver>,
+    query_param: Option<String>,
+    observer: Option<Observer>,
+    cookie_name: Option<String>,
+    language_header: Option<String>,
+    accept_param: Option<String>,
+    locale_from_path: bool,
+    trailing_slash: TrailingSlash,
+    strict_negotiation: bool,
+    strict_locale_segments: bool,
+    #[cfg(feature = "negotiation")]
+    negotiation: Option<NegotiationStrategy>,
 }
 
 macro_rules! builder_funcs {
     () => {
+        /// The supported languages, in the order they were given, Ex. for a language switcher
+        pub fn supported_languages(&self) -> &[LanguageIdentifier] {
+            self.supported_langs.as_slice()
+        }
+
+        /// Prefixes a path with the language sub-path of the redirect mode, as the free function
+        /// [localized_path], leaving the default language unprefixed with
+        /// `RedirectMode::PrefixNonDefaultOnly`.
+        pub fn localized_path(&self, locale: &LanguageIdentifier, path: &str) -> String {
+            prefixed_path(locale, path, &self.redirect_mode, Some(&self.default_lang))
+        }
+
         /// Change redirect settings of service
         pub fn redirect(self, redirect_mode: RedirectMode) -> Self {
             Self {
-                redirect_mode: redirect_mode,
+                redirect_mode,
                 ..self
             }
         }
 
         /// Exclude paths from redirect when in Redirect mode
-        /// Must use paths that start with `/`.
+        /// Paths should start with `/`, which is prepended to paths without it, Ex. `.well-known`
+        /// excludes `/.well-known`. Use [Self::try_excluded_paths] to reject them instead.
+        /// Paths are matched by whole segments, so `/api` excludes `/api/users` but not `/apidocs`.
         ///
         /// # Example
         /// ```ignore
@@ -59,13 +433,117 @@ macro_rules! builder_funcs {
         pub fn excluded_paths(self, paths_to_exclude: &[&str]) -> Self {
             Self {
                 excluded_paths: paths_to_exclude
-                    .into_iter()
-                    .map(|v| v.to_string())
+                    .iter()
+                    .map(|v| with_leading_slash(v))
+                    .collect(),
+                ..self
+            }
+        }
+
+        /// Exclude paths from redirect like [Self::excluded_paths], returning an error for the
+        /// first path that does not start with `/`
+        pub fn try_excluded_paths(self, paths_to_exclude: &[&str]) -> Result<Self, ConfigError> {
+            if let Some(path) = paths_to_exclude.iter().find(|path| !path.starts_with('/')) {
+                return Err(ConfigError::InvalidExcludedPath(path.to_string()));
+            }
+
+            Ok(self.excluded_paths(paths_to_exclude))
+        }
+
+        /// Exclude a path from redirect when in Redirect mode only for requests with the given
+        /// method, Ex. `POST` to `/webhooks` while `GET` is still localized.
+        /// Can be called several times, and combined with [Self::excluded_paths] to exclude
+        /// other paths for all methods.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+        /// )
+        /// .excluded_paths(&["/health"])
+        /// .excluded_path_method("/webhooks", http::Method::POST)
+        /// ```
+        pub fn excluded_path_method(mut self, path: &str, method: Method) -> Self {
+            self.excluded_path_methods
+                .push((with_leading_slash(path), method));
+
+            self
+        }
+
+        /// Never read these first path segments as a language, for routes named like a supported
+        /// language, Ex. `/en` as an abbreviation. Segments are given without `/`.<br>
+        /// The path `/en` is then localized as `/<lang>/en` in Redirect mode, including `/en/en`.
+        pub fn reserved_segments(self, segments: &[&str]) -> Self {
+            Self {
+                reserved_segments: segments.iter().map(|v| v.to_string()).collect(),
+                ..self
+            }
+        }
+
+        /// Read these first path segments as the given language, for legacy or shorthand URLs,
+        /// Ex. `/gb` for `en-GB` or `/cn` for `zh-Hans`. Segments are given without `/` and
+        /// matched case-insensitively before the segment is parsed as a language identifier.<br>
+        /// The language must be supported, and the segment is stripped from the path like a
+        /// language code.
+        pub fn path_aliases(self, aliases: &[(&str, LanguageIdentifier)]) -> Self {
+            Self {
+                path_aliases: aliases
+                    .iter()
+                    .map(|(segment, ident)| (segment.to_string(), ident.clone()))
                     .collect(),
                 ..self
             }
         }
 
+        /// Only redirect paths under these prefixes when in Redirect mode, passing through all
+        /// other paths.
+        /// Paths should start with `/`, which is prepended to paths without it, and are matched
+        /// by whole segments.
+        ///
+        /// Excluded paths take precedence, so a path that is both included and excluded is not
+        /// redirected.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+        /// ).included_paths(&["/docs", "/help"])
+        /// ```
+        pub fn included_paths(self, paths_to_include: &[&str]) -> Self {
+            Self {
+                included_paths: paths_to_include
+                    .iter()
+                    .map(|v| with_leading_slash(v))
+                    .collect(),
+                ..self
+            }
+        }
+
+        /// Exclude paths matching glob patterns from redirect when in Redirect mode.
+        /// Can be combined with [Self::excluded_paths].
+        ///
+        /// `*` matches within a path segment and `**` matches across segments.
+        /// Patterns not starting with `/` are matched against the last path segment.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+        /// ).excluded_paths_glob(&["/assets/**", "*.css"])
+        /// ```
+        pub fn excluded_paths_glob(self, patterns: &[&str]) -> Self {
+            Self {
+                excluded_globs: patterns.iter().map(|v| v.to_string()).collect(),
+                ..self
+            }
+        }
+
         /// Sets a redirect from the root domain to default locale as 301 (permanently moved).<br>
         /// Intended for websites introducing localization after initial release.
         pub fn redirect_default_as_301(self) -> Self {
@@ -74,425 +552,3010 @@ macro_rules! builder_funcs {
                 ..self
             }
         }
-    };
-}
 
-impl<S> LanguageIdentifierExtractor<S> {
-    pub fn new(
-        inner: S,
-        supported_langs: &[LanguageIdentifier],
-        default_lang: &LanguageIdentifier,
-    ) -> Self {
-        Self {
-            inner,
-            default_lang: default_lang.to_owned(),
-            redirect_mode: RedirectMode::NoRedirect,
-            supported_langs: supported_langs.to_owned(),
-            excluded_paths: Vec::new(),
-            redirect_default_as_301: false,
+        /// Redirects requests of all methods when in Redirect mode.<br>
+        /// By default only `GET` and `HEAD` requests are redirected, as browsers follow redirects
+        /// of other methods with a `GET`, dropping the request body. Other requests are passed
+        /// through with the language identifier from the Accept-Language header.
+        pub fn redirect_all_methods(self) -> Self {
+            Self {
+                redirect_all_methods: true,
+                ..self
+            }
         }
-    }
 
-    builder_funcs!();
+        /// Redirects to the language only in `RedirectMode::RedirectToFullLocaleSubPath` when the
+        /// requested locale is not one of the supported languages, Ex. `/en/` instead of
+        /// `/en-CA/` when only `en` is supported, so that URLs match the locale served.
+        pub fn collapse_full_locale_redirect(self) -> Self {
+            Self {
+                collapse_full_locale_redirect: true,
+                ..self
+            }
+        }
 
-    /// Unwraps the path and extracts language identifier if available.
-    /// Returns None if the LanguageIdentifier is not supported
-    fn lang_code_from_uri(&self, uri: &Uri) -> Option<LanguageIdentifier> {
-        let mut path_parts = uri.path().split('/');
-        path_parts.next();
+        /// Uses an absolute URL in the `Location` header of redirects, taking the authority from
+        /// the `Host` header of the request.<br>
+        /// The scheme is taken from the request URI if present, defaulting to `http`.
+        /// Falls back to a relative redirect if the request has no `Host` header.
+        pub fn absolute_redirect_from_host(self) -> Self {
+            Self {
+                redirect_base: RedirectBase::Host,
+                ..self
+            }
+        }
 
-        path_parts
-            .next()
-            .and_then(|code| code.parse::<LanguageIdentifier>().ok())
-            .and_then(|path_ident| {
-                if self.supported(&path_ident) {
-                    Some(path_ident)
-                } else {
-                    None
-                }
-            })
-    }
+        /// Uses an absolute URL in the `Location` header of redirects, prefixing the localized
+        /// path with the given base URL.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+        /// ).redirect_base_url("https://example.com")
+        /// ```
+        pub fn redirect_base_url(self, base_url: &str) -> Self {
+            Self {
+                redirect_base: RedirectBase::Url(base_url.trim_end_matches('/').to_string()),
+                ..self
+            }
+        }
 
-    /// Extracts language code from Accept-Language header if available and asks for at least one supported language
-    ///
-    /// # Details
-    /// All modern browsers send the Accept-Language header to tell a server what content it should send
-    ///
-    /// https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Accept-Language
-    fn lang_code_from_headers(&self, headers: &HeaderMap) -> Option<LanguageIdentifier> {
-        let accept_lang = headers
-            .get("Accept-Language")
-            .and_then(|val| val.to_str().ok())?;
-
-        accept_lang
-            .parse::<LanguageIdentifier>()
-            .ok()
-            .and_then(|ident| {
-                if self.supported(&ident) {
-                    Some(ident)
-                } else {
-                    None
-                }
-            })
-            .or_else(|| {
-                accept_lang
-                    .split(',')
-                    .filter(|part| !part.is_empty())
-                    // Strip the quality value
-                    .map(|part| part.find(|c| c == ';').map(|i| &part[..i]).unwrap_or(part))
-                    .filter_map(|ident_str| ident_str.parse::<LanguageIdentifier>().ok())
-                    .find(|ident| self.supported(ident))
-            })
-    }
+        /// Sends a body with redirect responses instead of an empty one, Ex. a link for clients
+        /// not following redirects. `{url}` in the template is replaced with the redirect
+        /// target, which is HTML-escaped if the content type is `text/html`.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+        /// ).redirect_body(
+        ///     r#"<a href="{url}">Continue</a>"#,
+        ///     HeaderValue::from_static("text/html; charset=utf-8"),
+        /// )
+        /// ```
+        pub fn redirect_body(self, template: &str, content_type: HeaderValue) -> Self {
+            Self {
+                redirect_body: Some(RedirectBody {
+                    template: template.to_string(),
+                    content_type,
+                }),
+                ..self
+            }
+        }
 
-    fn supported(&self, path_ident: &LanguageIdentifier) -> bool {
-        self.supported_langs
-            .iter()
-            .find(|ident| ident.language == path_ident.language)
-            .is_some()
-    }
+        /// Sets how requested language identifiers are matched against the supported languages.
+        /// Defaults to `MatchStrictness::Language`.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     langid!("zh-Hans"),
+        ///     vec![langid!("zh-Hans"), langid!("zh-Hant")],
+        ///     axum_l10n::RedirectMode::RedirectToFullLocaleSubPath,
+        /// ).match_strictness(axum_l10n::MatchStrictness::LanguageScript)
+        /// ```
+        pub fn match_strictness(self, match_strictness: MatchStrictness) -> Self {
+            Self {
+                match_strictness,
+                ..self
+            }
+        }
 
-    // Rewrites uri without the language code
-    fn rewrite_uri(
-        &self,
-        uri: &mut http::Uri,
-        ident: &LanguageIdentifier,
-    ) -> Result<(), http::uri::InvalidUri> {
-        let lang_code = match &self.redirect_mode {
-            RedirectMode::RedirectToFullLocaleSubPath => ident.to_string(),
-            RedirectMode::RedirectToLanguageSubPath => ident.language.to_string(),
-            RedirectMode::NoRedirect => unreachable!(),
-        };
+        /// Caches the language identifier resolved from up to `capacity` distinct
+        /// `Accept-Language` header values, evicting the least recently used.<br>
+        /// The cache is shared by all services created from the layer.
+        pub fn accept_language_cache(self, capacity: usize) -> Self {
+            Self {
+                header_cache: Some(HeaderCache::new(capacity)),
+                ..self
+            }
+        }
+
+        /// Resolves the language identifier with a custom function before the Accept-Language
+        /// header, for example from a user session inserted into the extensions by another
+        /// middleware.<br>
+        /// The header and default language are used if the function returns `None` or an
+        /// unsupported language. In Redirect modes, a language in the path takes precedence and
+        /// the function chooses the redirect target.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::NoRedirect,
+        /// ).with_resolver(|req| {
+        ///     req.headers
+        ///         .get("X-User-Language")
+        ///         .and_then(|val| val.to_str().ok())
+        ///         .and_then(|val| val.parse().ok())
+        /// })
+        /// ```
+        pub fn with_resolver<F>(self, resolver: F) -> Self
+        where
+            F: Fn(&http::request::Parts) -> Option<LanguageIdentifier> + Send + Sync + 'static,
+        {
+            Self {
+                resolver: Some(Resolver(Arc::new(resolver))),
+                ..self
+            }
+        }
+
+        /// Serves the alternate locale of an experiment to a fraction of sessions, as the custom
+        /// resolver. Replaces a resolver set with `with_resolver`.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, langid!("en-GB")],
+        ///     axum_l10n::RedirectMode::NoRedirect,
+        /// ).with_experiment(axum_l10n::LocaleExperiment::new(
+        ///     langid!("en-GB"),
+        ///     0.1,
+        ///     axum_l10n::SeedSource::Cookie("session".to_string()),
+        /// ))
+        /// ```
+        pub fn with_experiment(self, experiment: LocaleExperiment) -> Self {
+            self.with_resolver(move |req| experiment.resolve(req))
+        }
+
+        /// Reads the language identifier from a query parameter, Ex. `?lang=ja`, taking
+        /// precedence over the cookie, custom resolver and header when the language is
+        /// supported.<br>
+        /// In Redirect modes, requests with the parameter are redirected to the sub-path of its
+        /// language, without the parameter.
+        ///
+        /// # Example
+        /// ```ignore
+        /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::new(
+        ///     ENGLISH,
+        ///     vec![ENGLISH, JAPANESE],
+        ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+        /// ).locale_query_param("lang")
+        /// ```
+        pub fn locale_query_param(self, name: &str) -> Self {
+            Self {
+                query_param: Some(name.to_string()),
+                ..self
+            }
+        }
+
+        /// Reads the language identifier from a cookie, Ex. one set by a language switcher.<br>
+        /// The cookie takes precedence over the custom resolver and the Accept-Language header,
+        /// but not over the locale query parameter.
+        pub fn locale_cookie(self, name: &str) -> Self {
+            Self {
+                cookie_name: Some(name.to_string()),
+                ..self
+            }
+        }
+
+        /// Reads the preferred languages from another header than Accept-Language, Ex. `X-Locale`
+        /// set by a proxy that already negotiated the language. The value is parsed like an
+        /// Accept-Language header and the Accept-Language header is then ignored.
+        pub fn language_header(self, name: &str) -> Self {
+            Self {
+                language_header: Some(name.to_string()),
+                ..self
+            }
+        }
+
+        /// Reads the language from a parameter of the media types in the Accept header, Ex.
+        /// `ja` for `Accept: application/json; lang=ja` with `lang`, for API clients.<br>
+        /// The parameter is read before the Accept-Language header, and the first supported
+        /// language is used.
+        pub fn accept_media_type_param(self, name: &str) -> Self {
+            Self {
+                accept_param: Some(name.to_string()),
+                ..self
+            }
+        }
+
+        /// Reads the language identifier from the first path segment in
+        /// `RedirectMode::NoRedirect`, Ex. `ja` for `/ja/page`, before the other sources.<br>
+        /// The path is left untouched and requests without a supported language segment are not
+        /// redirected.
+        pub fn locale_from_path(self) -> Self {
+            Self {
+                locale_from_path: true,
+                ..self
+            }
+        }
+
+        /// Sets how trailing slashes are handled when redirecting to a localized path, so that
+        /// `/lists` and `/lists/` redirect to the same route. Defaults to
+        /// `TrailingSlash::Preserve`.
+        pub fn trailing_slash(self, trailing_slash: TrailingSlash) -> Self {
+            Self {
+                trailing_slash,
+                ..self
+            }
+        }
+
+        /// Registers an observer notified of redirects and resolved language identifiers.
+        pub fn observer(self, observer: impl L10nObserver + 'static) -> Self {
+            Self {
+                observer: Some(Observer(Arc::new(observer))),
+                ..self
+            }
+        }
+
+        /// Responds with `406 Not Acceptable`, listing the supported languages, when the
+        /// Accept-Language header matches none of them in `RedirectMode::NoRedirect`.<br>
+        /// Requests without the header, or accepting any language with `*`, still use the
+        /// default language.
+        pub fn strict_negotiation(self) -> Self {
+            Self {
+                strict_negotiation: true,
+                ..self
+            }
+        }
+
+        /// Responds with `404 Not Found` in the sub-path redirect modes when the first path
+        /// segment looks like a language identifier but is not supported, Ex. `/xx/page` or
+        /// `/xx-YY/page`, instead of redirecting it under a language.<br>
+        /// A segment looks like a language identifier if it parses as one with a language
+        /// subtag of two letters, or of three letters followed by other subtags. Routes named
+        /// like one can be kept with `reserved_segments`.
+        pub fn strict_locale_segments(self) -> Self {
+            Self {
+                strict_locale_segments: true,
+                ..self
+            }
+        }
+
+        /// Negotiates languages with the `fluent-langneg` algorithm instead of comparing
+        /// subtags, expanding likely subtags and matching regions and variants as ranges.<br>
+        /// The language identifier from the Accept-Language header is then the best supported
+        /// language, Ex. `zh-TW` resolves to a supported `zh-Hant`.
+        #[cfg(feature = "negotiation")]
+        pub fn negotiation_strategy(self, strategy: NegotiationStrategy) -> Self {
+            Self {
+                negotiation: Some(strategy),
+                ..self
+            }
+        }
+
+        /// Sets the `Content-Language` header of the response to the resolved language identifier
+        /// when in `RedirectMode::NoRedirect`.
+        pub fn set_content_language(self, content_language: bool) -> Self {
+            Self {
+                content_language,
+                ..self
+            }
+        }
+    };
+}
+
+impl<S> LanguageIdentifierExtractor<S> {
+    pub fn new(
+        inner: S,
+        supported_langs: &[LanguageIdentifier],
+        default_lang: &LanguageIdentifier,
+    ) -> Self {
+        Self {
+            inner,
+            default_lang: default_lang.to_owned(),
+            redirect_mode: RedirectMode::NoRedirect,
+            supported_langs: SupportedLanguages::new(supported_langs),
+            included_paths: Vec::new(),
+            excluded_paths: Vec::new(),
+            excluded_path_methods: Vec::new(),
+            reserved_segments: Vec::new(),
+            path_aliases: Vec::new(),
+            excluded_globs: Vec::new(),
+            redirect_default_as_301: false,
+            redirect_all_methods: false,
+            collapse_full_locale_redirect: false,
+            content_language: false,
+            redirect_base: RedirectBase::Relative,
+            redirect_body: None,
+            match_strictness: MatchStrictness::Language,
+            header_cache: None,
+            resolver: None,
+            query_param: None,
+            observer: None,
+            cookie_name: None,
+            language_header: None,
+            accept_param: None,
+            locale_from_path: false,
+            trailing_slash: TrailingSlash::Preserve,
+            strict_negotiation: false,
+            strict_locale_segments: false,
+            #[cfg(feature = "negotiation")]
+            negotiation: None,
+        }
+    }
+
+    builder_funcs!();
+
+    /// Unwraps the path and extracts language identifier if available.
+    /// Returns None if the LanguageIdentifier is not supported
+    fn lang_code_from_uri(&self, uri: &Uri) -> Option<LanguageIdentifier> {
+        let mut path_parts = uri.path().split('/');
+        path_parts.next();
+
+        path_parts
+            .next()
+            .map(percent_decode)
+            .filter(|_| !self.has_reserved_segment(uri.path()))
+            .and_then(|code| {
+                self.path_alias(&code)
+                    .cloned()
+                    .or_else(|| code.parse::<LanguageIdentifier>().ok())
+            })
+            .and_then(|path_ident| {
+                if self.supported(&path_ident) {
+                    Some(path_ident)
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Checks the first path segment is shaped like a language identifier, Ex. `xx` or `xx-YY`,
+    /// while not being a reserved segment or an alias
+    fn has_locale_shaped_segment(&self, uri: &Uri) -> bool {
+        let Some(segment) = uri.path().split('/').nth(1).map(percent_decode) else {
+            return false;
+        };
+        if self.has_reserved_segment(uri.path()) || self.path_alias(&segment).is_some() {
+            return false;
+        }
+
+        segment.parse::<LanguageIdentifier>().is_ok_and(|ident| {
+            match ident.language.as_str().len() {
+                2 => true,
+                3 => segment.contains('-'),
+                _ => false,
+            }
+        })
+    }
+
+    /// Checks the first path segment is already the language code of the redirect mode, and
+    /// not a reserved segment
+    fn has_lang_code_segment(&self, path: &str, ident: &LanguageIdentifier) -> bool {
+        path.split('/')
+            .nth(1)
+            .map(percent_decode)
+            .filter(|_| !self.has_reserved_segment(path))
+            .is_some_and(|segment| segment.eq_ignore_ascii_case(&self.path_lang_code(ident)))
+    }
+
+    /// Checks the first path segment was set with `reserved_segments`, unless it prefixes a
+    /// reserved segment as its language, Ex. `/en/en` for the reserved `/en` in `en`
+    fn has_reserved_segment(&self, path: &str) -> bool {
+        let is_reserved = |segment: Option<&str>| {
+            segment.map(percent_decode).is_some_and(|segment| {
+                self.reserved_segments
+                    .iter()
+                    .any(|reserved| *reserved == segment)
+            })
+        };
+        let mut segments = path.split('/').skip(1);
+
+        is_reserved(segments.next()) && !is_reserved(segments.next())
+    }
+
+    /// The language of a first path segment in the alias table
+    fn path_alias(&self, segment: &str) -> Option<&LanguageIdentifier> {
+        self.path_aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(segment))
+            .map(|(_, ident)| ident)
+    }
+
+    /// The value of the Accept-Language header, or of the header set with `language_header`
+    fn language_header_value<'h>(&self, headers: &'h HeaderMap) -> Option<&'h str> {
+        let name = self.language_header.as_deref().unwrap_or("Accept-Language");
+
+        headers.get(name).and_then(|val| val.to_str().ok())
+    }
+
+    /// Extracts language code from Accept-Language header if available and asks for at least one supported language
+    ///
+    /// Ranges are tried by quality, each resolving to its most specific supported language
+    /// identifier, Ex. `zh-Hant` rather than `zh-Hans` for `zh-Hant-TW`.
+    ///
+    /// # Details
+    /// All modern browsers send the Accept-Language header to tell a server what content it should send
+    ///
+    /// https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Accept-Language
+    fn lang_code_from_headers(&self, headers: &HeaderMap) -> Option<LanguageIdentifier> {
+        let accept_lang = self.language_header_value(headers)?;
+
+        match &self.header_cache {
+            Some(cache) => cache.get_or_insert_with(accept_lang, || {
+                self.lang_code_from_accept_language(accept_lang)
+            }),
+            None => self.lang_code_from_accept_language(accept_lang),
+        }
+    }
+
+    fn lang_code_from_accept_language(&self, accept_lang: &str) -> Option<LanguageIdentifier> {
+        #[cfg(feature = "negotiation")]
+        if let Some(strategy) = self.negotiation {
+            let requested = accept_language_ranges(accept_lang)
+                .filter_map(|tag| tag.parse::<LanguageIdentifier>().ok())
+                .collect::<Vec<_>>();
+
+            return self.supported_langs.negotiate(&requested, strategy);
+        }
+
+        accept_language_ranges(accept_lang)
+            .filter_map(|ident_str| ident_str.parse::<LanguageIdentifier>().ok())
+            .find_map(|ident| {
+                self.supported_langs
+                    .best_match(&ident, self.match_strictness)
+            })
+    }
+
+    /// Extracts language code from the media type parameter of the Accept header if set and
+    /// supported, Ex. `lang` in `application/json; lang=ja, text/html;q=0.5`
+    fn lang_code_from_accept(&self, headers: &HeaderMap) -> Option<LanguageIdentifier> {
+        let name = self.accept_param.as_ref()?;
+
+        headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|val| val.to_str().ok())
+            .flat_map(|accept| accept.split(','))
+            .flat_map(|media_type| media_type.split(';').skip(1))
+            .filter_map(|param| param.split_once('='))
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .filter_map(|(_, value)| value.trim().trim_matches('"').parse().ok())
+            .find(|ident| self.supported(ident))
+    }
+
+    /// Extracts language code from the locale query parameter if set and supported
+    fn lang_code_from_query(&self, uri: &Uri) -> Option<LanguageIdentifier> {
+        let name = self.query_param.as_ref()?;
+
+        uri.query()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| percent_decode(value).parse::<LanguageIdentifier>().ok())
+            .filter(|ident| self.supported(ident))
+    }
+
+    /// Extracts language code from the locale cookie if set and supported
+    fn lang_code_from_cookie(&self, headers: &HeaderMap) -> Option<LanguageIdentifier> {
+        let name = self.cookie_name.as_ref()?;
+
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|val| val.to_str().ok())
+            .flat_map(|cookies| cookies.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.trim_matches('"').parse::<LanguageIdentifier>().ok())
+            .filter(|ident| self.supported(ident))
+    }
+
+    /// Removes the locale query parameter from a query, returning None if nothing remains
+    fn query_without_locale_param<'a>(&self, query: &'a str) -> Option<Cow<'a, str>> {
+        let query = match &self.query_param {
+            Some(name) => Cow::Owned(
+                query
+                    .split('&')
+                    .filter(|pair| pair.split('=').next() != Some(name.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("&"),
+            ),
+            None => Cow::Borrowed(query),
+        };
+
+        if query.is_empty() {
+            None
+        } else {
+            Some(query)
+        }
+    }
+
+    /// Finds the preferred language of a request without a language in its path.
+    ///
+    /// Used both to resolve the language and to build redirects, with the precedence
+    /// query parameter > cookie > custom resolver > Accept-Language header > default.
+    fn preferred_lang(&self, req: &Parts) -> (LanguageIdentifier, LocaleSource) {
+        if let Some(ident) = self.lang_code_from_query(&req.uri) {
+            return (ident, LocaleSource::Query);
+        }
+
+        if let Some(ident) = self.lang_code_from_cookie(&req.headers) {
+            return (ident, LocaleSource::Cookie);
+        }
+
+        if let Some(ident) = self
+            .resolver
+            .as_ref()
+            .and_then(|resolver| (resolver.0)(req))
+            .filter(|ident| self.supported(ident))
+        {
+            return (ident, LocaleSource::Resolver);
+        }
+
+        if let Some(ident) = self.lang_code_from_accept(&req.headers) {
+            return (ident, LocaleSource::Header);
+        }
+
+        match self.lang_code_from_headers(&req.headers) {
+            Some(ident) => (ident, LocaleSource::Header),
+            None => (self.default_lang.clone(), LocaleSource::Default),
+        }
+    }
+
+    /// Checks the path is included, if an allowlist is set, and not excluded
+    fn should_redirect_path(&self, path: &str) -> bool {
+        let included = self.included_paths.is_empty()
+            || self
+                .included_paths
+                .iter()
+                .any(|included| path_has_prefix(path, included));
+
+        included && !self.is_excluded(path)
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.excluded_paths
+            .iter()
+            .any(|excluded| path_has_prefix(path, excluded))
+            || self
+                .excluded_globs
+                .iter()
+                .any(|pattern| glob::glob_matches(pattern, path))
+    }
+
+    fn is_excluded_for_method(&self, path: &str, method: &Method) -> bool {
+        self.excluded_path_methods
+            .iter()
+            .any(|(excluded, excluded_method)| {
+                excluded_method == method && path_has_prefix(path, excluded)
+            })
+    }
+
+    fn supported(&self, path_ident: &LanguageIdentifier) -> bool {
+        #[cfg(feature = "negotiation")]
+        if let Some(strategy) = self.negotiation {
+            return self
+                .supported_langs
+                .negotiate(std::slice::from_ref(path_ident), strategy)
+                .is_some();
+        }
+
+        self.supported_langs
+            .contains(path_ident, self.match_strictness)
+    }
+
+    // Rewrites uri without the language code
+    fn rewrite_uri(&self, uri: &mut http::Uri, ident: &LanguageIdentifier) {
+        let lang_code = self.path_lang_code(ident);
+
+        // Strip only the leading segment, treating a bare `/<lang>` as the root
+        let full_path = uri.path().strip_prefix('/').unwrap_or(uri.path());
+        let (segment, path) = match full_path.find('/') {
+            Some(i) => full_path.split_at(i),
+            None => (full_path, "/"),
+        };
+        // Language identifiers are parsed case-insensitively, Ex. `/EN-us` for `en-US`
+        let segment = percent_decode(segment);
+        if !segment.eq_ignore_ascii_case(&lang_code) && self.path_alias(&segment) != Some(ident) {
+            return;
+        }
+
+        // The path is a suffix of a valid path and the query is kept, so rebuilding cannot
+        // fail, and the URI would be left untouched if it did
+        let path_and_query = match uri.query() {
+            Some(q) => PathAndQuery::try_from(format!("{path}?{q}")),
+            None => PathAndQuery::try_from(path),
+        };
+        let Ok(path_and_query) = path_and_query else {
+            return;
+        };
+
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = Some(path_and_query);
+        if let Ok(rewritten) = Uri::from_parts(parts) {
+            *uri = rewritten;
+        }
+    }
+
+    /// Redirecting would turn the request into a GET and lose its body
+    fn can_redirect_method(&self, method: &Method) -> bool {
+        self.redirect_all_methods || matches!(*method, Method::GET | Method::HEAD)
+    }
+
+    /// The language code used in the path for the redirect mode
+    fn path_lang_code(&self, ident: &LanguageIdentifier) -> String {
+        self.redirect_mode
+            .path_lang_code(ident)
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    /// Checks the Accept-Language header is set and does not accept any language
+    fn rejects_accept_language(&self, headers: &HeaderMap) -> bool {
+        self.language_header_value(headers)
+            .is_some_and(|accept_lang| !accept_language_ranges(accept_lang).any(|tag| tag == "*"))
+    }
+
+    fn not_found_response(&self) -> axum::response::Response {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(http::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(axum::body::Body::from(
+                "The language in the path is not available",
+            ))
+            .expect("Valid response")
+    }
+
+    fn not_acceptable_response(&self) -> axum::response::Response {
+        let available = self
+            .supported_langs
+            .as_slice()
+            .iter()
+            .map(|ident| ident.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Response::builder()
+            .status(StatusCode::NOT_ACCEPTABLE)
+            .header(http::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(axum::body::Body::from(format!(
+                "None of the accepted languages are available. Available languages: {}",
+                available
+            )))
+            .expect("Valid response")
+    }
+
+    fn report_resolution(
+        &self,
+        ident: &LanguageIdentifier,
+        source: LocaleSource,
+        redirected: bool,
+    ) {
+        trace_resolution(ident, source, redirected);
+
+        if let Some(observer) = &self.observer {
+            observer.0.on_resolved(ident, source);
+        }
+    }
+
+    /// Returns None if the redirect path is not a valid `Location` header, ex. with a base URL
+    /// containing control characters
+    fn redirect_response(&self, req: &Parts) -> Option<axum::response::Response> {
+        let (new_path, ident, source) = self.build_redirect_path(req);
+        // A default language that is not supported is not found in the path it redirects to,
+        // so redirecting again would loop, Ex. `/fr/page` to `/fr/fr/page`
+        if self.has_lang_code_segment(req.uri.path(), &ident) {
+            return None;
+        }
+        let location = HeaderValue::try_from(&new_path).ok()?;
+        self.report_resolution(&ident, source, true);
+        if let Some(observer) = &self.observer {
+            let from = req
+                .uri
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/");
+            observer.0.on_redirect(from, &new_path);
+        }
+
+        let mut response = Response::builder()
+            .status(
+                // Send 301 if the redirect is for the base page and the redirect
+                // is to the page marked as the default language
+                if self.redirect_default_as_301
+                    && req.uri.path() == "/"
+                    && ident.language == self.default_lang.language
+                {
+                    StatusCode::MOVED_PERMANENTLY
+                } else {
+                    StatusCode::FOUND
+                },
+            )
+            .header(http::header::LOCATION, location);
+
+        let body = match &self.redirect_body {
+            Some(redirect_body) => {
+                response = response.header(
+                    http::header::CONTENT_TYPE,
+                    redirect_body.content_type.clone(),
+                );
+
+                axum::body::Body::from(redirect_body.render(&new_path))
+            }
+            None => axum::body::Body::empty(),
+        };
+
+        response.body(body).ok()
+    }
+
+    fn build_redirect_path(&self, req: &Parts) -> (String, LanguageIdentifier, LocaleSource) {
+        let mut new_path = match &self.redirect_base {
+            RedirectBase::Relative => String::new(),
+            RedirectBase::Host => req
+                .headers
+                .get(http::header::HOST)
+                .and_then(|host| host.to_str().ok())
+                .map(|host| format!("{}://{}", req.uri.scheme_str().unwrap_or("http"), host))
+                .unwrap_or_default(),
+            RedirectBase::Url(base_url) => base_url.clone(),
+        };
+
+        let (mut ident, source) = self.preferred_lang(req);
+        if self.collapse_full_locale_redirect
+            && !self
+                .supported_langs
+                .contains(&ident, MatchStrictness::FullLocale)
+        {
+            ident = LanguageIdentifier::from_parts(ident.language, None, None, &[]);
+        }
+        let path = req.uri.path();
+        let path = match self.trailing_slash {
+            TrailingSlash::Preserve => Cow::Borrowed(path),
+            TrailingSlash::Strip => Cow::Borrowed(path.trim_end_matches('/')),
+            TrailingSlash::Add => Cow::Owned(format!("{}/", path.trim_end_matches('/'))),
+        };
+        new_path.push_str(&self.localized_path(&ident, &path));
+
+        if let Some(q) = req
+            .uri
+            .query()
+            .and_then(|q| self.query_without_locale_param(q))
+        {
+            new_path.push('?');
+            new_path.push_str(&q);
+        }
+
+        (new_path, ident, source)
+    }
+}
+
+/// Emits a debug event recording how the language identifier of a request was resolved
+#[cfg(feature = "tracing")]
+fn trace_resolution(ident: &LanguageIdentifier, source: LocaleSource, redirected: bool) {
+    tracing::debug!(
+        locale = %ident,
+        source = ?source,
+        redirected,
+        "resolved language identifier"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+fn trace_resolution(_ident: &LanguageIdentifier, _source: LocaleSource, _redirected: bool) {}
+
+/// Percent-decodes a path segment, returning it unchanged if it is not valid percent-encoded UTF-8.
+fn percent_decode(segment: &str) -> Cow<'_, str> {
+    if !segment.contains('%') {
+        return Cow::Borrowed(segment);
+    }
+
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = segment
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match byte {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                None => return Cow::Borrowed(segment),
+            }
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    match String::from_utf8(decoded) {
+        Ok(decoded) => Cow::Owned(decoded),
+        Err(_) => Cow::Borrowed(segment),
+    }
+}
+
+/// Checks the path starts with the prefix, only matching whole path segments.
+/// Ex. `/api` matches `/api` and `/api/users`, but not `/apidocs`
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'))
+}
+
+impl<S, B> Service<http::Request<B>> for LanguageIdentifierExtractor<S>
+where
+    S: Service<http::Request<B>, Response = axum::response::Response> + Send + 'static + Clone,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+    type Response = axum::response::Response;
+
+    /// No back pressure needed
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let (mut parts, body) = req.into_parts();
+
+        match &self.redirect_mode {
+            &RedirectMode::NoRedirect => {
+                let path_ident = self
+                    .locale_from_path
+                    .then(|| self.lang_code_from_uri(&parts.uri))
+                    .flatten();
+                let (ident, source) = match (upstream_locale(&parts.extensions), path_ident) {
+                    (Some(upstream), _) => upstream,
+                    (None, Some(ident)) => (ident, LocaleSource::Uri),
+                    (None, None) => self.preferred_lang(&parts),
+                };
+
+                if self.strict_negotiation
+                    && source == LocaleSource::Default
+                    && self.rejects_accept_language(&parts.headers)
+                {
+                    let response = self.not_acceptable_response();
+
+                    return Box::pin(async move { Ok(response) });
+                }
+                self.report_resolution(&ident, source, false);
+
+                if !self.content_language {
+                    insert_locale(&mut parts.extensions, ident, source);
+
+                    return Box::pin(self.inner.call(http::Request::from_parts(parts, body)));
+                }
+
+                let content_language = HeaderValue::from_str(&ident.to_string());
+                insert_locale(&mut parts.extensions, ident, source);
+                let future = self.inner.call(http::Request::from_parts(parts, body));
+
+                Box::pin(async move {
+                    let mut response = future.await?;
+
+                    if let Ok(content_language) = content_language {
+                        response
+                            .headers_mut()
+                            .insert(CONTENT_LANGUAGE, content_language);
+                    }
+
+                    Ok(response)
+                })
+            }
+            RedirectMode::RedirectToFullLocaleSubPath
+            | RedirectMode::RedirectToLanguageSubPath
+            | RedirectMode::PrefixNonDefaultOnly => {
+                if let Some(ident) = self.lang_code_from_uri(&parts.uri) {
+                    // Remove lang code from path for matching in axum
+                    let localized_uri = LocalizedUri(parts.uri.clone());
+                    self.rewrite_uri(&mut parts.uri, &ident);
+
+                    // Switch to the sub-path of a different language requested in the query
+                    let upstream = upstream_locale(&parts.extensions);
+                    let query_ident = self.lang_code_from_query(&parts.uri);
+                    if upstream.is_none()
+                        && query_ident.is_some_and(|query_ident| {
+                            self.path_lang_code(&query_ident) != self.path_lang_code(&ident)
+                        })
+                        && self.can_redirect_method(&parts.method)
+                    {
+                        if let Some(response) = self.redirect_response(&parts) {
+                            return Box::pin(async move { Ok(response) });
+                        }
+                    }
+
+                    let (ident, source) = upstream.unwrap_or((ident, LocaleSource::Uri));
+                    self.report_resolution(&ident, source, false);
+
+                    insert_locale(&mut parts.extensions, ident, source);
+                    parts.extensions.insert(localized_uri);
+
+                    Box::pin(self.inner.call(http::Request::from_parts(parts, body)))
+                } else {
+                    // Do not redirect if in excluded paths, or not in included paths
+                    let path = parts.uri.path();
+                    if !self.should_redirect_path(path)
+                        || self.is_excluded_for_method(path, &parts.method)
+                    {
+                        return Box::pin(self.inner.call(http::Request::from_parts(parts, body)));
+                    }
+
+                    // A language decided upstream is served without redirecting
+                    if let Some((ident, source)) = upstream_locale(&parts.extensions) {
+                        self.report_resolution(&ident, source, false);
+                        insert_locale(&mut parts.extensions, ident, source);
+
+                        return Box::pin(self.inner.call(http::Request::from_parts(parts, body)));
+                    }
+
+                    // Unsupported languages are not redirected under another language
+                    if self.strict_locale_segments && self.has_locale_shaped_segment(&parts.uri) {
+                        let response = self.not_found_response();
+
+                        return Box::pin(async move { Ok(response) });
+                    }
+
+                    if let RedirectMode::PrefixNonDefaultOnly = self.redirect_mode {
+                        // Paths without a language are in the default language, unless
+                        // another language is requested in the query
+                        let query_ident = self.lang_code_from_query(&parts.uri);
+                        if query_ident.is_some_and(|query_ident| {
+                            query_ident.language != self.default_lang.language
+                        }) && self.can_redirect_method(&parts.method)
+                        {
+                            if let Some(response) = self.redirect_response(&parts) {
+                                return Box::pin(async move { Ok(response) });
+                            }
+                        }
+
+                        let ident = self.default_lang.clone();
+                        self.report_resolution(&ident, LocaleSource::Default, false);
+                        insert_locale(&mut parts.extensions, ident, LocaleSource::Default);
+
+                        return Box::pin(self.inner.call(http::Request::from_parts(parts, body)));
+                    }
+
+                    if self.can_redirect_method(&parts.method) {
+                        if let Some(response) = self.redirect_response(&parts) {
+                            return Box::pin(async move { Ok(response) });
+                        }
+                    }
+
+                    // Pass the request through if it cannot be redirected
+                    let (ident, source) = self.preferred_lang(&parts);
+                    self.report_resolution(&ident, source, false);
+                    insert_locale(&mut parts.extensions, ident, source);
+
+                    Box::pin(self.inner.call(http::Request::from_parts(parts, body)))
+                }
+            }
+        }
+    }
+}
+
+/// Splits an Accept-Language header into its language ranges without their quality values or
+/// surrounding whitespace, Ex. `EN-us` and `JA` for `JA;q=0.8, EN-us`.
+///
+/// Ranges are ordered by quality, keeping the header order for equal qualities. Ranges with a
+/// quality of `0` are not acceptable and are skipped, and invalid qualities are read as `1`.
+/// Casing is normalized when the ranges are parsed into language identifiers.
+fn accept_language_ranges(accept_lang: &str) -> impl Iterator<Item = &str> {
+    let mut ranges = accept_lang
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let range = params.next()?.trim();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            (!range.is_empty() && quality > 0.0).then_some((range, quality))
+        })
+        .collect::<Vec<_>>();
+    // Stable, so that ranges of equal quality keep their order
+    ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    ranges.into_iter().map(|(range, _)| range)
+}
+
+/// Inserts the resolved language identifier into the request extensions, both wrapped in
+/// [ResolvedLocale] and bare for handlers extracting `Extension<LanguageIdentifier>`, with
+/// where it was found
+fn insert_locale(
+    extensions: &mut http::Extensions,
+    ident: LanguageIdentifier,
+    source: LocaleSource,
+) {
+    extensions.insert(ResolvedLocale(ident.clone()));
+    extensions.insert(ident);
+    extensions.insert(source);
+}
+
+/// The language identifier inserted into the request extensions by an upstream layer, as a
+/// [ResolvedLocale] or bare, with where it was found if known
+fn upstream_locale(extensions: &http::Extensions) -> Option<(LanguageIdentifier, LocaleSource)> {
+    let ident = extensions
+        .get::<ResolvedLocale>()
+        .map(|resolved| resolved.0.clone())
+        .or_else(|| extensions.get::<LanguageIdentifier>().cloned())?;
+    let source = extensions
+        .get::<LocaleSource>()
+        .copied()
+        .unwrap_or(LocaleSource::Upstream);
+
+    Some((ident, source))
+}
+
+#[derive(Debug, Clone)]
+pub struct LanguageIdentifierExtractorLayer {
+    default_lang: LanguageIdentifier,
+    supported_langs: Vec<LanguageIdentifier>,
+    redirect_mode: RedirectMode,
+    included_paths: Vec<String>,
+    excluded_paths: Vec<String>,
+    excluded_path_methods: Vec<(String, Method)>,
+    reserved_segments: Vec<String>,
+    path_aliases: Vec<(String, LanguageIdentifier)>,
+    excluded_globs: Vec<String>,
+    redirect_default_as_301: bool,
+    redirect_all_methods: bool,
+    collapse_full_locale_redirect: bool,
+    content_language: bool,
+    redirect_base: RedirectBase,
+    redirect_body: Option<RedirectBody>,
+    match_strictness: MatchStrictness,
+    header_cache: Option<HeaderCache>,
+    resolver: Option<Resolver>,
+    query_param: Option<String>,
+    observer: Option<Observer>,
+    cookie_name: Option<String>,
+    language_header: Option<String>,
+    accept_param: Option<String>,
+    locale_from_path: bool,
+    trailing_slash: TrailingSlash,
+    strict_negotiation: bool,
+    strict_locale_segments: bool,
+    #[cfg(feature = "negotiation")]
+    negotiation: Option<NegotiationStrategy>,
+}
+
+impl LanguageIdentifierExtractorLayer {
+    pub fn new(
+        default_lang: LanguageIdentifier,
+        supported_langs: Vec<LanguageIdentifier>,
+        redirect_mode: RedirectMode,
+    ) -> Self {
+        Self {
+            default_lang,
+            supported_langs,
+            redirect_mode,
+            included_paths: Vec::new(),
+            excluded_paths: Vec::new(),
+            excluded_path_methods: Vec::new(),
+            reserved_segments: Vec::new(),
+            path_aliases: Vec::new(),
+            excluded_globs: Vec::new(),
+            redirect_default_as_301: false,
+            redirect_all_methods: false,
+            collapse_full_locale_redirect: false,
+            content_language: false,
+            redirect_base: RedirectBase::Relative,
+            redirect_body: None,
+            match_strictness: MatchStrictness::Language,
+            header_cache: None,
+            resolver: None,
+            query_param: None,
+            observer: None,
+            cookie_name: None,
+            language_header: None,
+            accept_param: None,
+            locale_from_path: false,
+            trailing_slash: TrailingSlash::Preserve,
+            strict_negotiation: false,
+            strict_locale_segments: false,
+            #[cfg(feature = "negotiation")]
+            negotiation: None,
+        }
+    }
+
+    /// Creates the layer, checking the default language is one of the supported languages.
+    ///
+    /// # Example
+    /// ```
+    /// use unic_langid::langid;
+    ///
+    /// let err = axum_l10n::LanguageIdentifierExtractorLayer::try_new(
+    ///     langid!("fr"),
+    ///     vec![langid!("en"), langid!("ja")],
+    ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+    /// );
+    ///
+    /// assert!(err.is_err());
+    /// ```
+    pub fn try_new(
+        default_lang: LanguageIdentifier,
+        supported_langs: Vec<LanguageIdentifier>,
+        redirect_mode: RedirectMode,
+    ) -> Result<Self, ConfigError> {
+        if !supported_langs.contains(&default_lang) {
+            return Err(ConfigError::UnsupportedDefaultLanguage(default_lang));
+        }
+
+        Ok(Self::new(default_lang, supported_langs, redirect_mode))
+    }
+
+    /// Creates the layer from language tags, so that callers do not need to parse them.
+    /// Like [Self::try_new], the default language must be one of the supported languages.
+    ///
+    /// # Example
+    /// ```
+    /// let layer = axum_l10n::LanguageIdentifierExtractorLayer::from_str_slice(
+    ///     "en",
+    ///     &["en", "ja", "fr"],
+    ///     axum_l10n::RedirectMode::RedirectToLanguageSubPath,
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn from_str_slice(
+        default_lang: &str,
+        supported_langs: &[&str],
+        redirect_mode: RedirectMode,
+    ) -> Result<Self, ConfigError> {
+        let default_lang = parse_language_tag(default_lang)?;
+        let supported_langs = supported_langs
+            .iter()
+            .map(|tag| parse_language_tag(tag))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::try_new(default_lang, supported_langs, redirect_mode)
+    }
+
+    /// Creates a tera function listing the alternate links of a path for the supported
+    /// languages, see [HreflangAlternates].
+    #[cfg(feature = "tera")]
+    pub fn hreflang_alternates(&self) -> HreflangAlternates {
+        HreflangAlternates::new(self.supported_langs.clone(), self.redirect_mode.clone())
+            .default_language(self.default_lang.clone())
+    }
+
+    builder_funcs!();
+}
+
+impl<S> Layer<S> for LanguageIdentifierExtractorLayer {
+    type Service = LanguageIdentifierExtractor<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LanguageIdentifierExtractor {
+            inner,
+            default_lang: self.default_lang.clone(),
+            supported_langs: SupportedLanguages::new(&self.supported_langs),
+            redirect_mode: self.redirect_mode.clone(),
+            included_paths: self.included_paths.clone(),
+            excluded_paths: self.excluded_paths.clone(),
+            excluded_path_methods: self.excluded_path_methods.clone(),
+            reserved_segments: self.reserved_segments.clone(),
+            path_aliases: self.path_aliases.clone(),
+            excluded_globs: self.excluded_globs.clone(),
+            redirect_default_as_301: self.redirect_default_as_301,
+            redirect_all_methods: self.redirect_all_methods,
+            collapse_full_locale_redirect: self.collapse_full_locale_redirect,
+            content_language: self.content_language,
+            redirect_base: self.redirect_base.clone(),
+            redirect_body: self.redirect_body.clone(),
+            match_strictness: self.match_strictness,
+            header_cache: self.header_cache.clone(),
+            resolver: self.resolver.clone(),
+            query_param: self.query_param.clone(),
+            observer: self.observer.clone(),
+            cookie_name: self.cookie_name.clone(),
+            language_header: self.language_header.clone(),
+            accept_param: self.accept_param.clone(),
+            locale_from_path: self.locale_from_path,
+            trailing_slash: self.trailing_slash,
+            strict_negotiation: self.strict_negotiation,
+            strict_locale_segments: self.strict_locale_segments,
+            #[cfg(feature = "negotiation")]
+            negotiation: self.negotiation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, str::FromStr};
+
+    use http::HeaderValue;
+    use unic_langid::langid;
+
+    pub const ENGLISH: LanguageIdentifier = langid!("en");
+    pub const JAPANESE: LanguageIdentifier = langid!("ja");
+
+    use super::*;
+
+    struct DummyInner;
+
+    fn get_serv() -> LanguageIdentifierExtractor<DummyInner> {
+        let supported = vec![ENGLISH, JAPANESE];
+        LanguageIdentifierExtractor::new(DummyInner, &supported, &ENGLISH).redirect_default_as_301()
+    }
+
+    /// Service responding with the language identifier found in the request extensions
+    fn get_echo_serv() -> LanguageIdentifierExtractor<
+        impl Service<
+                http::Request<()>,
+                Response = axum::response::Response,
+                Error = Infallible,
+                Future = impl Send,
+            > + Clone
+            + Send,
+    > {
+        let inner = tower::service_fn(|req: http::Request<()>| async move {
+            let lang = req
+                .extensions()
+                .get::<LanguageIdentifier>()
+                .map(|lang| lang.to_string())
+                .unwrap_or_default();
+
+            Ok::<_, Infallible>(axum::response::Response::new(axum::body::Body::from(lang)))
+        });
+
+        LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH)
+    }
+
+    async fn body_string(response: axum::response::Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn can_rewrite_uri_full() {
+        let mut uri = "http://localhost:3000/en-US/lists".parse::<Uri>().unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToFullLocaleSubPath;
+
+        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+
+        service.rewrite_uri(&mut uri, &ident);
+
+        assert_eq!("http://localhost:3000/lists", uri.to_string().as_str());
+    }
+
+    #[test]
+    fn can_rewrite_uri_lang_only() {
+        let mut uri = "http://localhost:3000/en/lists".parse::<Uri>().unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+
+        service.rewrite_uri(&mut uri, &ident);
+
+        assert_eq!("http://localhost:3000/lists", uri.to_string().as_str());
+    }
+
+    #[test]
+    fn can_rewrite_uri_same_starting_text() {
+        let mut uri = "http://localhost:3000/en/enrollment/details"
+            .parse::<Uri>()
+            .unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+
+        service.rewrite_uri(&mut uri, &ident);
+
+        assert_eq!(
+            "http://localhost:3000/enrollment/details",
+            uri.to_string().as_str()
+        );
+    }
+
+    #[test]
+    fn can_rewrite_uri_with_query_params() {
+        let mut uri = "http://localhost:3000/en/?page=1".parse::<Uri>().unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+
+        service.rewrite_uri(&mut uri, &ident);
+
+        assert_eq!("http://localhost:3000/?page=1", uri.to_string().as_str());
+    }
+
+    #[test]
+    fn can_rewrite_uri_bare_locale() {
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        for (uri, expected) in [
+            ("http://localhost:3000/en", "http://localhost:3000/"),
+            ("http://localhost:3000/en/", "http://localhost:3000/"),
+            ("http://localhost:3000/en?q=1", "http://localhost:3000/?q=1"),
+            ("/en", "/"),
+        ] {
+            let mut uri = uri.parse::<Uri>().unwrap();
+
+            service.rewrite_uri(&mut uri, &ENGLISH);
+
+            assert_eq!(expected, uri.to_string().as_str());
+        }
+    }
+
+    #[test]
+    fn rewrite_uri_leaves_query_untouched() {
+        let mut uri = "http://localhost:3000/en/search?q=/en/"
+            .parse::<Uri>()
+            .unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        service.rewrite_uri(&mut uri, &ENGLISH);
+
+        assert_eq!(
+            "http://localhost:3000/search?q=/en/",
+            uri.to_string().as_str()
+        );
+    }
+
+    #[test]
+    fn rewrite_uri_only_strips_leading_segment() {
+        let mut uri = "/lists/en/details".parse::<Uri>().unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        service.rewrite_uri(&mut uri, &ENGLISH);
+
+        assert_eq!("/lists/en/details", uri.to_string().as_str());
+    }
+
+    #[test]
+    fn can_get_lang_code_from_percent_encoded_uri() {
+        let uri = "http://localhost:3000/en%2DUS/lists"
+            .parse::<Uri>()
+            .unwrap();
+        let supported = vec![langid!("en-US"), JAPANESE];
+        let service = LanguageIdentifierExtractor::new(DummyInner, &supported, &JAPANESE)
+            .redirect(RedirectMode::RedirectToFullLocaleSubPath);
+
+        assert_eq!(service.lang_code_from_uri(&uri), Some(langid!("en-US")));
+
+        let mut uri = uri;
+        service.rewrite_uri(&mut uri, &langid!("en-US"));
+
+        assert_eq!("http://localhost:3000/lists", uri.to_string().as_str());
+    }
+
+    #[test]
+    fn invalid_percent_encoding_is_ignored() {
+        let service = get_serv();
+
+        for path in [
+            "/en%2/lists",
+            "/en%ZZlists",
+            "/%C3%28/lists",
+            "/de%2DDE/lists",
+        ] {
+            let uri = path.parse::<Uri>().unwrap();
+            assert_eq!(service.lang_code_from_uri(&uri), None, "{path}");
+        }
+    }
+
+    fn get_chinese_serv(strictness: MatchStrictness) -> LanguageIdentifierExtractor<DummyInner> {
+        let supported = vec![langid!("zh-Hans"), langid!("zh-Hant")];
+        LanguageIdentifierExtractor::new(DummyInner, &supported, &langid!("zh-Hans"))
+            .match_strictness(strictness)
+    }
+
+    #[test]
+    fn language_strictness_ignores_script() {
+        let service = get_chinese_serv(MatchStrictness::Language);
+
+        assert!(service.supported(&langid!("zh-Hans")));
+        assert!(service.supported(&langid!("zh-Hant")));
+        assert!(service.supported(&langid!("zh")));
+        assert!(service.supported(&langid!("zh-Latn")));
+    }
+
+    #[test]
+    fn language_script_strictness_compares_script() {
+        let service = get_chinese_serv(MatchStrictness::LanguageScript);
+
+        assert!(service.supported(&langid!("zh-Hans")));
+        assert!(service.supported(&langid!("zh-Hant-TW")));
+        assert!(service.supported(&langid!("zh")));
+        assert!(!service.supported(&langid!("zh-Latn")));
+
+        let supported = vec![langid!("zh-Hans")];
+        let service = LanguageIdentifierExtractor::new(DummyInner, &supported, &langid!("zh-Hans"))
+            .match_strictness(MatchStrictness::LanguageScript);
+
+        assert!(!service.supported(&langid!("zh-Hant")));
+    }
+
+    #[test]
+    fn full_locale_strictness_compares_all_subtags() {
+        let service = get_chinese_serv(MatchStrictness::FullLocale);
+
+        assert!(service.supported(&langid!("zh-Hans")));
+        assert!(service.supported(&langid!("zh-Hant")));
+        assert!(!service.supported(&langid!("zh")));
+        assert!(!service.supported(&langid!("zh-Hant-TW")));
+    }
+
+    #[test]
+    fn can_get_lang_code_from_bare_locale_uri() {
+        let uri = "http://localhost:3000/ja?q=1".parse::<Uri>().unwrap();
+
+        let service = get_serv();
+
+        assert_eq!(service.lang_code_from_uri(&uri), Some(JAPANESE));
+    }
+
+    #[test]
+    fn can_redirect_with_query_params() {
+        let uri = "http://localhost:3000/?page=1".parse::<Uri>().unwrap();
+        let req = http::Request::builder()
+            .uri(uri)
+            .header("Accept-Language", "en-US,en;q=0.5")
+            .body(())
+            .unwrap();
+
+        let mut service = get_serv();
+        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+
+        let (new_path, ..) = service.build_redirect_path(&req.into_parts().0);
+
+        assert_eq!("/en/?page=1", new_path.as_str());
+    }
+
+    #[test]
+    fn redirect_path_is_relative_by_default() {
+        let req = http::Request::builder()
+            .uri("/lists%20all?q=a%2Fb")
+            .header("Host", "example.com")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+
+        let service = get_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
+
+        let (new_path, ..) = service.build_redirect_path(&req.into_parts().0);
+
+        assert_eq!("/ja/lists%20all?q=a%2Fb", new_path.as_str());
+    }
+
+    #[test]
+    fn can_redirect_to_absolute_url_from_host() {
+        let req = http::Request::builder()
+            .uri("/lists%20all?q=a%2Fb")
+            .header("Host", "example.com:3000")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+
+        let service = get_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .absolute_redirect_from_host();
+
+        let (new_path, ..) = service.build_redirect_path(&req.into_parts().0);
+
+        assert_eq!(
+            "http://example.com:3000/ja/lists%20all?q=a%2Fb",
+            new_path.as_str()
+        );
+    }
+
+    #[test]
+    fn can_redirect_to_absolute_base_url() {
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Host", "internal:8080")
+            .body(())
+            .unwrap();
+
+        let service = get_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .redirect_base_url("https://example.com/");
+
+        let (new_path, ..) = service.build_redirect_path(&req.into_parts().0);
+
+        assert_eq!("https://example.com/en/lists", new_path.as_str());
+    }
+
+    #[test]
+    fn can_get_supported_lang_code_from_uri() {
+        let uri = "http://localhost:3000/ja/lists".parse::<Uri>().unwrap();
+
+        let service = get_serv();
+
+        let ident = service.lang_code_from_uri(&uri);
+
+        assert!(ident.is_some());
+        assert_eq!(ident.unwrap(), JAPANESE)
+    }
+
+    #[test]
+    fn unsupported_lang_code_from_uri() {
+        let uri = "http://localhost:3000/de/lists".parse::<Uri>().unwrap();
+
+        let service = get_serv();
+
+        let ident = service.lang_code_from_uri(&uri);
+
+        assert!(ident.is_none());
+    }
+
+    #[test]
+    fn can_extract_lang_header_single() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept-Language", HeaderValue::from_static("en"));
+
+        let service = get_serv();
+
+        let ident = service.lang_code_from_headers(&headers).unwrap();
+
+        let target = "en".parse::<LanguageIdentifier>().unwrap();
+        assert_eq!(ident, target)
+    }
+
+    #[test]
+    fn can_extract_lang_header_compound() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept-Language", HeaderValue::from_static("en-US"));
+
+        let service = get_serv();
+
+        let ident = service.lang_code_from_headers(&headers).unwrap();
+
+        // The supported language is returned rather than the requested locale
+        assert_eq!(ident, ENGLISH)
+    }
+
+    #[test]
+    fn can_extract_lang_header_compound_with_quality_val() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Accept-Language",
+            HeaderValue::from_static("en-US,en;q=0.5"),
+        );
+
+        let service = get_serv();
+
+        let ident = service.lang_code_from_headers(&headers).unwrap();
+
+        let target = "en-US".parse::<LanguageIdentifier>().unwrap();
+        assert_eq!(ident.language, target.language)
+    }
+
+    #[tokio::test]
+    async fn sets_content_language_header() {
+        let inner = tower::service_fn(|_req: http::Request<()>| async {
+            Ok::<_, Infallible>(axum::response::Response::new(axum::body::Body::from(
+                "handler body",
+            )))
+        });
+        let mut service = LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH)
+            .set_content_language(true);
+
+        let req = http::Request::builder()
+            .uri("http://localhost:3000/lists")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(
+            response.headers().get(CONTENT_LANGUAGE),
+            Some(&HeaderValue::from_static("ja"))
+        );
+    }
+
+    #[tokio::test]
+    async fn content_language_header_not_set_by_default() {
+        let inner = tower::service_fn(|_req: http::Request<()>| async {
+            Ok::<_, Infallible>(axum::response::Response::default())
+        });
+        let mut service = LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH);
+
+        let req = http::Request::builder()
+            .uri("http://localhost:3000/lists")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+
+        let response = service.call(req).await.unwrap();
+
+        assert!(response.headers().get(CONTENT_LANGUAGE).is_none());
+    }
+
+    #[test]
+    fn can_exclude_paths() {
+        let service = get_serv().excluded_paths(&["/api", "/health"]);
+
+        assert!(service.is_excluded("/health"));
+        assert!(service.is_excluded("/api/users"));
+        assert!(!service.is_excluded("/apixyz"));
+        assert!(!service.is_excluded("/lists"));
+    }
+
+    #[test]
+    fn excluded_paths_respect_segments() {
+        let service = get_serv().excluded_paths(&["/en", "/assets/"]);
+
+        assert!(service.is_excluded("/en"));
+        assert!(service.is_excluded("/en/"));
+        assert!(service.is_excluded("/en/details"));
+        assert!(!service.is_excluded("/enrollment"));
+        assert!(service.is_excluded("/assets/main.css"));
+    }
+
+    #[test]
+    fn can_include_paths() {
+        let service = get_serv()
+            .included_paths(&["/docs", "help"])
+            .excluded_paths(&["/docs/internal"]);
+
+        assert!(service.should_redirect_path("/docs"));
+        assert!(service.should_redirect_path("/help"));
+        assert!(service.should_redirect_path("/help/faq"));
+        assert!(!service.should_redirect_path("/docs/internal/notes"));
+        assert!(!service.should_redirect_path("/api/users"));
+        assert!(!service.should_redirect_path("/"));
+    }
+
+    #[test]
+    fn redirects_all_paths_without_includes() {
+        let service = get_serv().excluded_paths(&["/api"]);
+
+        assert!(service.should_redirect_path("/"));
+        assert!(service.should_redirect_path("/lists"));
+        assert!(!service.should_redirect_path("/api"));
+    }
+
+    #[test]
+    fn can_exclude_paths_glob() {
+        let service = get_serv().excluded_paths_glob(&["/api/**", "*.css", "/health"]);
+
+        assert!(service.is_excluded("/health"));
+        assert!(service.is_excluded("/api/users"));
+        assert!(service.is_excluded("/assets/main.css"));
+        assert!(!service.is_excluded("/apixyz"));
+        assert!(!service.is_excluded("/healthz"));
+    }
+
+    #[tokio::test]
+    async fn passes_through_post_without_redirect() {
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
+
+        let req = http::Request::builder()
+            .method(Method::POST)
+            .uri("/submit")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "ja");
+
+        let req = http::Request::builder()
+            .method(Method::GET)
+            .uri("/submit")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/ja/submit");
+    }
+
+    #[tokio::test]
+    async fn can_redirect_all_methods() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .redirect_all_methods();
+
+        let req = http::Request::builder()
+            .method(Method::POST)
+            .uri("/submit")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/en/submit");
+    }
+
+    #[test]
+    fn accept_language_ranges_follow_quality() {
+        assert_eq!(
+            accept_language_ranges("zh;q=0.5, zh-Hant;q=0.9, zh-Hant-TW").collect::<Vec<_>>(),
+            ["zh-Hant-TW", "zh-Hant", "zh"]
+        );
+        assert_eq!(
+            accept_language_ranges("ja;q=0.8, fr;q=0, en, de;q=0.8, ko;q=x").collect::<Vec<_>>(),
+            ["en", "ko", "ja", "de"]
+        );
+
+        let mut service = get_serv();
+        service.supported_langs =
+            SupportedLanguages::new(&[langid!("zh-Hans"), langid!("zh-Hant"), ENGLISH]);
+
+        for (accept_lang, target) in [
+            ("zh-Hant-TW, zh-Hant;q=0.9, zh;q=0.5", langid!("zh-Hant")),
+            ("zh-Hans-CN, zh-Hant;q=0.9", langid!("zh-Hans")),
+            // Higher quality ranges win over more specific ones
+            ("zh-Hans;q=0.4, zh-Hant-TW;q=0.9", langid!("zh-Hant")),
+            ("zh-Hant-TW;q=0.5, en;q=0.9", ENGLISH),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                "Accept-Language",
+                HeaderValue::from_str(accept_lang).unwrap(),
+            );
+
+            assert_eq!(
+                service.lang_code_from_headers(&headers),
+                Some(target),
+                "{accept_lang}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn redirects_to_most_specific_supported_variant() {
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToFullLocaleSubPath);
+        service.supported_langs =
+            SupportedLanguages::new(&[langid!("zh-Hans"), langid!("zh-Hant")]);
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "zh-Hant-TW, zh-Hant;q=0.9, zh;q=0.5")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers().get("Location").unwrap(),
+            "/zh-Hant/lists"
+        );
+    }
+
+    #[test]
+    fn can_extract_lang_header_with_whitespace_and_casing() {
+        let service = get_serv();
+
+        for (accept_lang, target) in [
+            ("EN-us , JA", ENGLISH),
+            ("  JA  ", JAPANESE),
+            ("  De ,  Ja-jp ;q=0.8,en;q=0.5", JAPANESE),
+            ("fr;q=0.9 ,\tEN", ENGLISH),
+            (" , ja", JAPANESE),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                "Accept-Language",
+                HeaderValue::from_str(accept_lang).unwrap(),
+            );
+
+            assert_eq!(
+                service.lang_code_from_headers(&headers),
+                Some(target),
+                "{accept_lang}"
+            );
+        }
+    }
+
+    #[test]
+    fn can_extract_lang_header_wildcard() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept-Language", HeaderValue::from_static("*"));
+    }
+
+    #[test]
+    fn can_create_layer_from_str_slice() {
+        let layer = LanguageIdentifierExtractorLayer::from_str_slice(
+            "en",
+            &["en", "ja", "fr"],
+            RedirectMode::NoRedirect,
+        )
+        .unwrap();
+
+        assert_eq!(layer.default_lang, ENGLISH);
+        assert_eq!(
+            layer.supported_langs,
+            vec![ENGLISH, JAPANESE, langid!("fr")]
+        );
+    }
+
+    #[test]
+    fn invalid_tag_in_str_slice_is_named() {
+        let err = LanguageIdentifierExtractorLayer::from_str_slice(
+            "en",
+            &["en", "not a tag"],
+            RedirectMode::NoRedirect,
+        )
+        .unwrap_err();
+
+        assert!(matches!(&err, ConfigError::InvalidLanguageTag { tag, .. } if tag == "not a tag"));
+        assert!(err.to_string().contains("'not a tag'"));
+    }
+
+    #[test]
+    fn unsupported_default_lang_is_rejected() {
+        let err = LanguageIdentifierExtractorLayer::try_new(
+            langid!("fr"),
+            vec![ENGLISH, JAPANESE],
+            RedirectMode::RedirectToLanguageSubPath,
+        )
+        .unwrap_err();
+
+        assert!(
+            matches!(err, ConfigError::UnsupportedDefaultLanguage(lang) if lang == langid!("fr"))
+        );
+
+        let err = LanguageIdentifierExtractorLayer::from_str_slice(
+            "fr",
+            &["en", "ja"],
+            RedirectMode::NoRedirect,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, ConfigError::UnsupportedDefaultLanguage(_)));
+    }
+
+    #[tokio::test]
+    async fn excluded_paths_without_leading_slash_are_normalized() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .excluded_paths(&[".well-known", "/api"]);
+
+        for path in ["/.well-known/security.txt", "/api/users"] {
+            let req = http::Request::builder().uri(path).body(()).unwrap();
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+        }
+    }
+
+    #[test]
+    fn excluded_paths_without_leading_slash_are_rejected() {
+        let err = LanguageIdentifierExtractorLayer::new(
+            ENGLISH,
+            vec![ENGLISH, JAPANESE],
+            RedirectMode::RedirectToLanguageSubPath,
+        )
+        .try_excluded_paths(&["/api", "well-known"])
+        .unwrap_err();
+
+        assert!(matches!(&err, ConfigError::InvalidExcludedPath(path) if path == "well-known"));
+        assert!(err.to_string().contains("'well-known'"));
+
+        assert!(get_serv().try_excluded_paths(&["/api", "/assets"]).is_ok());
+    }
+
+    #[test]
+    fn can_try_new_with_supported_default_lang() {
+        let layer = LanguageIdentifierExtractorLayer::try_new(
+            JAPANESE,
+            vec![ENGLISH, JAPANESE],
+            RedirectMode::RedirectToLanguageSubPath,
+        );
+
+        assert!(layer.is_ok());
+    }
+
+    #[test]
+    fn second_identical_header_hits_cache() {
+        let service = get_serv().accept_language_cache(8);
+        let cache = service.header_cache.clone().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept-Language", HeaderValue::from_static("ja,en;q=0.5"));
+
+        assert_eq!(service.lang_code_from_headers(&headers), Some(JAPANESE));
+        assert!(cache.contains("ja,en;q=0.5"));
+
+        // A cached result is returned without parsing the header again
+        cache.insert("ja,en;q=0.5", Some(ENGLISH));
+        assert_eq!(service.lang_code_from_headers(&headers), Some(ENGLISH));
+    }
+
+    fn user_language_header(req: &Parts) -> Option<LanguageIdentifier> {
+        req.headers
+            .get("X-User-Language")
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.parse().ok())
+    }
+
+    #[tokio::test]
+    async fn resolver_takes_precedence_over_header() {
+        let mut service = get_echo_serv().with_resolver(user_language_header);
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "en")
+            .header("X-User-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "ja");
+
+        // Falls back to the header for unsupported languages
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "en")
+            .header("X-User-Language", "de")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "en");
+    }
+
+    #[tokio::test]
+    async fn experiment_serves_alternate_locale() {
+        let mut service = get_echo_serv().with_experiment(
+            LocaleExperiment::new(JAPANESE, 1.0, SeedSource::Cookie("session".to_string())).seed(7),
+        );
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "en")
+            .header("Cookie", "session=abc")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "ja");
+
+        // Requests without a session are not part of the experiment
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "en");
+    }
+
+    #[tokio::test]
+    async fn resolver_chooses_redirect_target() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .with_resolver(user_language_header);
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "en")
+            .header("X-User-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/ja/lists");
+    }
+
+    #[tokio::test]
+    async fn query_param_takes_precedence() {
+        let mut service = get_echo_serv().locale_query_param("lang");
+
+        let req = http::Request::builder()
+            .uri("/lists?lang=ja")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "ja");
+    }
+
+    #[tokio::test]
+    async fn unsupported_query_param_is_ignored() {
+        let mut service = get_echo_serv().locale_query_param("lang");
+
+        let req = http::Request::builder()
+            .uri("/lists?lang=de")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "ja");
+    }
+
+    #[tokio::test]
+    async fn query_param_redirects_to_locale_sub_path() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .locale_query_param("lang");
+
+        let req = http::Request::builder()
+            .uri("/lists?page=2&lang=ja")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers().get("Location").unwrap(),
+            "/ja/lists?page=2"
+        );
+
+        // Switches language from a localized path
+        let req = http::Request::builder()
+            .uri("/en/lists?lang=ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/ja/lists");
+
+        // Does not redirect when already on the requested language
+        let req = http::Request::builder()
+            .uri("/ja/lists?lang=ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "ja");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn emits_resolution_event() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        /// Counts the events emitted by the service
+        struct EventCounter(Arc<AtomicUsize>);
+
+        impl tracing::Subscriber for EventCounter {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+                tracing::span::Id::from_u64(1)
+            }
+            fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+            fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+            fn event(&self, event: &tracing::Event<'_>) {
+                if event.metadata().level() == &tracing::Level::DEBUG {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            fn enter(&self, _: &tracing::span::Id) {}
+            fn exit(&self, _: &tracing::span::Id) {}
+        }
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
+
+        tracing::subscriber::with_default(EventCounter(count.clone()), || {
+            // The event is emitted when the request is handled, before the future is polled
+            let req = http::Request::builder().uri("/lists").body(()).unwrap();
+            drop(service.call(req));
+            let req = http::Request::builder().uri("/en/lists").body(()).unwrap();
+            drop(service.call(req));
+        });
+
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[derive(Default)]
+    struct MockObserver {
+        redirects: std::sync::Mutex<Vec<(String, String)>>,
+        resolved: std::sync::Mutex<Vec<(LanguageIdentifier, LocaleSource)>>,
+    }
+
+    impl L10nObserver for Arc<MockObserver> {
+        fn on_redirect(&self, from: &str, to: &str) {
+            self.redirects
+                .lock()
+                .unwrap()
+                .push((from.to_string(), to.to_string()));
+        }
+
+        fn on_resolved(&self, locale: &LanguageIdentifier, source: LocaleSource) {
+            self.resolved.lock().unwrap().push((locale.clone(), source));
+        }
+    }
+
+    #[tokio::test]
+    async fn observer_is_notified_of_redirect() {
+        let observer = Arc::new(MockObserver::default());
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .observer(observer.clone());
+
+        let req = http::Request::builder()
+            .uri("/lists?page=1")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        service.call(req).await.unwrap();
+
+        assert_eq!(
+            *observer.redirects.lock().unwrap(),
+            vec![("/lists?page=1".to_string(), "/ja/lists?page=1".to_string())]
+        );
+        assert_eq!(
+            *observer.resolved.lock().unwrap(),
+            vec![(JAPANESE, LocaleSource::Header)]
+        );
+    }
+
+    #[tokio::test]
+    async fn observer_is_notified_of_resolution() {
+        let observer = Arc::new(MockObserver::default());
+        let mut service = get_echo_serv().observer(observer.clone());
+
+        let req = http::Request::builder().uri("/lists").body(()).unwrap();
+        service.call(req).await.unwrap();
+
+        assert!(observer.redirects.lock().unwrap().is_empty());
+        assert_eq!(
+            *observer.resolved.lock().unwrap(),
+            vec![(ENGLISH, LocaleSource::Default)]
+        );
+    }
+
+    #[tokio::test]
+    async fn strict_negotiation_rejects_unsupported_languages() {
+        let mut service = get_echo_serv().strict_negotiation();
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "de-DE,fr;q=0.5")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(
+            body_string(response).await,
+            "None of the accepted languages are available. Available languages: en, ja"
+        );
+    }
+
+    #[tokio::test]
+    async fn strict_negotiation_allows_missing_or_wildcard_header() {
+        let mut service = get_echo_serv().strict_negotiation();
+
+        let req = http::Request::builder().uri("/lists").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
+        assert_eq!(body_string(response).await, "en");
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "de, *;q=0.1")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+        assert_eq!(body_string(response).await, "en");
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "de,ja;q=0.5")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+        assert_eq!(body_string(response).await, "ja");
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_default_without_strict_negotiation() {
+        let mut service = get_echo_serv();
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "de-DE")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "en");
+    }
+
+    #[cfg(feature = "negotiation")]
+    #[test]
+    fn negotiation_resolves_supported_locale() {
+        let supported = vec![langid!("es-MX"), langid!("zh-Hans"), langid!("zh-Hant")];
+        let naive = LanguageIdentifierExtractor::new(DummyInner, &supported, &langid!("es-MX"));
+        let negotiated =
+            LanguageIdentifierExtractor::new(DummyInner, &supported, &langid!("es-MX"))
+                .negotiation_strategy(NegotiationStrategy::Filtering);
+
+        for (header, naive_ident, negotiated_ident) in [
+            ("es-419", langid!("es-MX"), langid!("es-MX")),
+            // Without likely subtags, the region does not imply the script
+            ("zh-TW", langid!("zh-Hans"), langid!("zh-Hant")),
+            ("zh-CN", langid!("zh-Hans"), langid!("zh-Hans")),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert("Accept-Language", HeaderValue::from_static(header));
+
+            assert_eq!(naive.lang_code_from_headers(&headers), Some(naive_ident));
+            assert_eq!(
+                negotiated.lang_code_from_headers(&headers),
+                Some(negotiated_ident)
+            );
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Accept-Language",
+            HeaderValue::from_static("de,zh-TW;q=0.5"),
+        );
+        assert_eq!(
+            negotiated.lang_code_from_headers(&headers),
+            Some(langid!("zh-Hant"))
+        );
+    }
+
+    /// Service using every source of the precedence chain
+    fn get_precedence_serv() -> LanguageIdentifierExtractor<
+        impl Service<
+                http::Request<()>,
+                Response = axum::response::Response,
+                Error = Infallible,
+                Future = impl Send,
+            > + Clone
+            + Send,
+    > {
+        let supported = vec![
+            ENGLISH,
+            JAPANESE,
+            langid!("fr"),
+            langid!("de"),
+            langid!("es"),
+        ];
+        let inner = get_echo_serv().inner;
+
+        LanguageIdentifierExtractor::new(inner, &supported, &ENGLISH)
+            .locale_query_param("lang")
+            .locale_cookie("lang")
+            .with_resolver(user_language_header)
+    }
+
+    #[tokio::test]
+    async fn precedence_chain_is_shared_by_resolution_and_redirects() {
+        let cases = [
+            // query > cookie > resolver > header > default
+            ("/lists?lang=ja", "lang=fr", "de", "es", "ja"),
+            ("/lists", "theme=dark; lang=fr", "de", "es", "fr"),
+            ("/lists", "", "de", "es", "de"),
+            ("/lists", "", "", "es", "es"),
+            ("/lists", "", "", "", "en"),
+            // Unsupported values fall through to the next source
+            ("/lists?lang=it", "lang=it", "it", "es", "es"),
+        ];
+
+        for (uri, cookie, user_lang, accept_lang, expected) in cases {
+            let build = || {
+                let mut req = http::Request::builder().uri(uri);
+                for (name, value) in [
+                    ("Cookie", cookie),
+                    ("X-User-Language", user_lang),
+                    ("Accept-Language", accept_lang),
+                ] {
+                    if !value.is_empty() {
+                        req = req.header(name, value);
+                    }
+                }
+                req.body(()).unwrap()
+            };
+
+            let response = get_precedence_serv().call(build()).await.unwrap();
+            assert_eq!(body_string(response).await, expected, "{uri} {cookie}");
+
+            let response = get_precedence_serv()
+                .redirect(RedirectMode::RedirectToLanguageSubPath)
+                .call(build())
+                .await
+                .unwrap();
+            let location = response.headers().get("Location").unwrap();
+            assert_eq!(
+                location.to_str().unwrap(),
+                format!("/{expected}/lists"),
+                "{uri} {cookie}"
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_slash_policies() {
+        let cases = [
+            (TrailingSlash::Preserve, "/lists", "/en/lists"),
+            (TrailingSlash::Preserve, "/lists/", "/en/lists/"),
+            (TrailingSlash::Strip, "/lists", "/en/lists"),
+            (TrailingSlash::Strip, "/lists/", "/en/lists"),
+            (TrailingSlash::Strip, "/", "/en"),
+            (TrailingSlash::Add, "/lists", "/en/lists/"),
+            (TrailingSlash::Add, "/lists/", "/en/lists/"),
+            (TrailingSlash::Add, "/", "/en/"),
+        ];
+
+        for (policy, path, expected) in cases {
+            let service = get_serv()
+                .redirect(RedirectMode::RedirectToLanguageSubPath)
+                .trailing_slash(policy);
+            let req = http::Request::builder()
+                .uri(format!("{path}?page=1"))
+                .body(())
+                .unwrap();
+
+            let (new_path, ..) = service.build_redirect_path(&req.into_parts().0);
+
+            assert_eq!(new_path, format!("{expected}?page=1"), "{policy:?} {path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_redirect_location_passes_through() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .redirect_base_url("https://example.com\n");
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "ja");
+
+        let req = http::Request::builder()
+            .uri("/en/lists?lang=ja")
+            .body(())
+            .unwrap();
+        let response = service.locale_query_param("lang").call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "en");
+    }
+
+    #[tokio::test]
+    async fn can_read_custom_language_header() {
+        let mut service = get_echo_serv().language_header("X-Locale");
+
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("X-Locale", "ja;q=0.9,en;q=0.5")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "ja");
+
+        // Accept-Language is ignored when the custom header is set
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "en");
+    }
+
+    #[tokio::test]
+    async fn no_redirect_can_read_locale_from_path() {
+        let mut service = get_echo_serv().locale_from_path();
+
+        for (path, expected) in [("/ja/page", "ja"), ("/page", "en"), ("/de/page", "en")] {
+            let req = http::Request::builder()
+                .uri(path)
+                .header("Accept-Language", "en")
+                .body(())
+                .unwrap();
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+            assert_eq!(body_string(response).await, expected, "{path}");
+        }
+
+        // The path is ignored by default
+        let req = http::Request::builder()
+            .uri("/ja/page")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = get_echo_serv().call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "en");
+    }
+
+    #[tokio::test]
+    async fn inserts_localized_uri() {
+        let inner = tower::service_fn(|req: http::Request<()>| async move {
+            let localized = req
+                .extensions()
+                .get::<LocalizedUri>()
+                .map(|LocalizedUri(uri)| uri.to_string())
+                .unwrap_or_default();
+
+            Ok::<_, Infallible>(axum::response::Response::new(axum::body::Body::from(
+                format!("{} {}", req.uri(), localized),
+            )))
+        });
+        let mut service = LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH)
+            .redirect(RedirectMode::RedirectToLanguageSubPath);
+
+        let req = http::Request::builder()
+            .uri("/ja/products/42?page=2")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(
+            body_string(response).await,
+            "/products/42?page=2 /ja/products/42?page=2"
+        );
+
+        // Not inserted when the path is not stripped
+        let mut service = service.excluded_paths(&["/api"]);
+        let req = http::Request::builder().uri("/api").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(body_string(response).await, "/api ");
+    }
+
+    #[tokio::test]
+    async fn inserts_resolved_locale() {
+        let inner = tower::service_fn(|req: http::Request<()>| async move {
+            let lang = req
+                .extensions()
+                .get::<ResolvedLocale>()
+                .map(|ResolvedLocale(lang)| lang.to_string())
+                .unwrap_or_default();
+
+            Ok::<_, Infallible>(axum::response::Response::new(axum::body::Body::from(lang)))
+        });
+
+        let mut service = LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH);
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let new_uri = uri
-            .to_string()
-            .replacen(&format!("/{}/", lang_code), "/", 1);
-        *uri = http::Uri::try_from(new_uri)?;
+        assert_eq!(body_string(response).await, "ja");
 
-        Ok(())
-    }
+        let mut service = LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH)
+            .redirect(RedirectMode::RedirectToLanguageSubPath);
 
-    fn build_redirect_path<B>(&self, req: &http::Request<B>) -> (String, LanguageIdentifier) {
-        let mut new_path = String::from("/");
+        let req = http::Request::builder().uri("/ja/lists").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let ident = if let Some(preferred_ident) = self.lang_code_from_headers(req.headers()) {
-            preferred_ident
-        } else {
-            self.default_lang.clone()
-        };
-        let ident_string = match self.redirect_mode {
-            RedirectMode::RedirectToFullLocaleSubPath => ident.to_string(),
-            RedirectMode::RedirectToLanguageSubPath => ident.language.to_string(),
-            _ => unreachable!(),
-        };
+        assert_eq!(body_string(response).await, "ja");
 
-        new_path.push_str(&ident_string);
-        new_path.push_str(req.uri().path());
+        let mut service = service.excluded_paths(&["/api"]);
+        let req = http::Request::builder().uri("/api").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        if let Some(q) = req.uri().query() {
-            new_path.push_str("?");
-            new_path.push_str(q);
-        }
+        assert_eq!(body_string(response).await, "");
+    }
+
+    #[test]
+    fn can_build_localized_paths() {
+        let locale = langid!("en-US");
 
-        (new_path, ident)
+        assert_eq!(
+            localized_path(
+                &locale,
+                "/products/42",
+                &RedirectMode::RedirectToLanguageSubPath
+            ),
+            "/en/products/42"
+        );
+        assert_eq!(
+            localized_path(
+                &locale,
+                "/products/42?page=2",
+                &RedirectMode::RedirectToFullLocaleSubPath
+            ),
+            "/en-US/products/42?page=2"
+        );
+        assert_eq!(
+            localized_path(&JAPANESE, "/", &RedirectMode::RedirectToLanguageSubPath),
+            "/ja/"
+        );
+        assert_eq!(
+            localized_path(&JAPANESE, "about", &RedirectMode::RedirectToLanguageSubPath),
+            "/ja/about"
+        );
+        assert_eq!(
+            localized_path(&JAPANESE, "/about", &RedirectMode::NoRedirect),
+            "/about"
+        );
     }
-}
 
-impl<S, B> Service<http::Request<B>> for LanguageIdentifierExtractor<S>
-where
-    S: Service<http::Request<B>, Response = axum::response::Response> + Send + 'static + Clone,
-    S::Future: Send + 'static,
-    B: Send + 'static,
-{
-    type Error = S::Error;
-    type Future =
-        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
-    type Response = axum::response::Response;
+    #[test]
+    fn can_read_supported_languages() {
+        let supported = vec![JAPANESE, ENGLISH, langid!("fr")];
+        let layer = LanguageIdentifierExtractorLayer::new(
+            JAPANESE,
+            supported.clone(),
+            RedirectMode::NoRedirect,
+        );
 
-    /// No back pressure needed
-    fn poll_ready(
-        &mut self,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx)
+        assert_eq!(layer.supported_languages(), supported.as_slice());
+        assert_eq!(
+            layer.layer(DummyInner).supported_languages(),
+            supported.as_slice()
+        );
     }
 
-    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
-        let headers = req.headers();
+    #[tokio::test]
+    async fn prefix_non_default_only_serves_default_at_root() {
+        let inner = tower::service_fn(|req: http::Request<()>| async move {
+            let lang = req
+                .extensions()
+                .get::<LanguageIdentifier>()
+                .map(|lang| lang.to_string())
+                .unwrap_or_default();
+
+            Ok::<_, Infallible>(axum::response::Response::new(axum::body::Body::from(
+                format!("{} {}", lang, req.uri()),
+            )))
+        });
+        let mut service = LanguageIdentifierExtractor::new(inner, &[ENGLISH, JAPANESE], &ENGLISH)
+            .redirect(RedirectMode::PrefixNonDefaultOnly)
+            .locale_query_param("lang");
+
+        // Not redirected, even if another language is accepted
+        let req = http::Request::builder()
+            .uri("/about")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let lang_ident = match &self.redirect_mode {
-            RedirectMode::NoRedirect => self.lang_code_from_headers(headers),
-            RedirectMode::RedirectToLanguageSubPath | RedirectMode::RedirectToFullLocaleSubPath => {
-                self.lang_code_from_uri(req.uri())
-            }
-        };
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "en /about");
 
-        match &self.redirect_mode {
-            &RedirectMode::NoRedirect => {
-                let ident = match lang_ident {
-                    Some(ident) => ident,
-                    None => self.default_lang.clone(),
-                };
+        let req = http::Request::builder().uri("/ja/about").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-                req.extensions_mut().insert(ident);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "ja /about");
 
-                Box::pin(self.inner.call(req))
-            }
-            RedirectMode::RedirectToFullLocaleSubPath | RedirectMode::RedirectToLanguageSubPath => {
-                if let Some(ident) = lang_ident {
-                    // Remove lang code from path for matching in axum
-                    let uri = req.uri_mut();
-                    self.rewrite_uri(uri, &ident).expect("invalid url");
+        let req = http::Request::builder()
+            .uri("/about?lang=ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-                    req.extensions_mut().insert(ident);
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/ja/about");
 
-                    Box::pin(self.inner.call(req))
-                } else {
-                    // Do not redirect if in excluded paths
-                    let path = req.uri().path();
-                    if self
-                        .excluded_paths
-                        .iter()
-                        .any(|excluded| path.starts_with(excluded))
-                    {
-                        return Box::pin(self.inner.call(req));
-                    }
+        // Switching to the default language removes the prefix
+        let req = http::Request::builder()
+            .uri("/ja/about?lang=en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-                    let (new_path, ident) = self.build_redirect_path(&req);
-
-                    let response = Response::builder()
-                        .status(
-                            // Send 301 if the redirect is for the base page and the redirect
-                            // is to the page marked as the default language
-                            if self.redirect_default_as_301
-                                && path == "/"
-                                && ident.language == self.default_lang.language
-                            {
-                                StatusCode::MOVED_PERMANENTLY
-                            } else {
-                                StatusCode::FOUND
-                            },
-                        )
-                        .header("Location", new_path)
-                        .body(axum::body::Body::empty())
-                        .expect("Valid response");
-
-                    Box::pin(async move { Ok(response) })
-                }
-            }
-        }
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/about");
+
+        assert_eq!(service.localized_path(&ENGLISH, "/about"), "/about");
+        assert_eq!(service.localized_path(&JAPANESE, "/about"), "/ja/about");
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct LanguageIdentifierExtractorLayer {
-    default_lang: LanguageIdentifier,
-    supported_langs: Vec<LanguageIdentifier>,
-    redirect_mode: RedirectMode,
-    excluded_paths: Vec<String>,
-    redirect_default_as_301: bool,
-}
+    #[tokio::test]
+    async fn reserved_segments_are_not_stripped() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .reserved_segments(&["en"]);
 
-impl LanguageIdentifierExtractorLayer {
-    pub fn new(
-        default_lang: LanguageIdentifier,
-        supported_langs: Vec<LanguageIdentifier>,
-        redirect_mode: RedirectMode,
-    ) -> Self {
-        Self {
-            default_lang,
-            supported_langs,
-            redirect_mode,
-            excluded_paths: Vec::new(),
-            redirect_default_as_301: false,
-        }
-    }
+        assert_eq!(service.lang_code_from_uri(&"/en".parse().unwrap()), None);
 
-    builder_funcs!();
-}
+        // The route is localized under a language segment
+        let req = http::Request::builder()
+            .uri("/en")
+            .header("Accept-Language", "ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-impl<S> Layer<S> for LanguageIdentifierExtractorLayer {
-    type Service = LanguageIdentifierExtractor<S>;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/ja/en");
 
-    fn layer(&self, inner: S) -> Self::Service {
-        LanguageIdentifierExtractor {
-            inner,
-            default_lang: self.default_lang.clone(),
-            supported_langs: self.supported_langs.clone(),
-            redirect_mode: self.redirect_mode.clone(),
-            excluded_paths: self.excluded_paths.clone(),
-            redirect_default_as_301: self.redirect_default_as_301,
-        }
+        let mut uri = "/ja/en".parse::<Uri>().unwrap();
+        service.rewrite_uri(&mut uri, &JAPANESE);
+
+        assert_eq!(uri, "/en");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+    #[tokio::test]
+    async fn reserved_segment_matching_preferred_language_is_redirected() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .reserved_segments(&["en"]);
 
-    use http::HeaderValue;
-    use unic_langid::langid;
+        // The reserved route is not mistaken for an existing language prefix
+        let req = http::Request::builder()
+            .uri("/en")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-    pub const ENGLISH: LanguageIdentifier = langid!("en");
-    pub const JAPANESE: LanguageIdentifier = langid!("ja");
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/en/en");
 
-    use super::*;
+        // The localized route is served without redirecting again
+        let req = http::Request::builder()
+            .uri("/en/en")
+            .header("Accept-Language", "en")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-    struct DummyInner;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "en");
 
-    fn get_serv() -> LanguageIdentifierExtractor<DummyInner> {
-        let supported = vec![ENGLISH, JAPANESE];
-        LanguageIdentifierExtractor::new(DummyInner, &supported, &ENGLISH).redirect_default_as_301()
+        let mut uri = "/en/en".parse::<Uri>().unwrap();
+        service.rewrite_uri(&mut uri, &ENGLISH);
+
+        assert_eq!(uri, "/en");
     }
 
-    #[test]
-    fn can_rewrite_uri_full() {
-        let mut uri = "http://localhost:3000/en-US/lists".parse::<Uri>().unwrap();
+    #[tokio::test]
+    async fn path_aliases_map_to_languages() {
+        let british = langid!("en-GB");
+        let chinese = langid!("zh-Hans");
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToFullLocaleSubPath)
+            .path_aliases(&[("gb", british.clone()), ("cn", chinese.clone())]);
+        service.supported_langs = SupportedLanguages::new(&[ENGLISH, british.clone(), chinese]);
 
-        let mut service = get_serv();
-        service.redirect_mode = RedirectMode::RedirectToFullLocaleSubPath;
+        assert_eq!(
+            service.lang_code_from_uri(&"/gb/lists".parse().unwrap()),
+            Some(british.clone())
+        );
+        assert_eq!(
+            service.lang_code_from_uri(&"/CN".parse().unwrap()),
+            Some(langid!("zh-Hans"))
+        );
+        // Standard language identifiers are still parsed
+        assert_eq!(
+            service.lang_code_from_uri(&"/en-GB/lists".parse().unwrap()),
+            Some(british.clone())
+        );
 
-        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+        let mut uri = "/gb/lists?page=2".parse::<Uri>().unwrap();
+        service.rewrite_uri(&mut uri, &british);
 
-        service.rewrite_uri(&mut uri, &ident).unwrap();
+        assert_eq!(uri, "/lists?page=2");
 
-        assert_eq!("http://localhost:3000/lists", uri.to_string().as_str());
+        for (path, lang) in [("/gb/lists", "en-GB"), ("/cn/", "zh-Hans")] {
+            let req = http::Request::builder().uri(path).body(()).unwrap();
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+            assert_eq!(body_string(response).await, lang, "{path}");
+        }
     }
 
     #[test]
-    fn can_rewrite_uri_lang_only() {
-        let mut uri = "http://localhost:3000/en/lists".parse::<Uri>().unwrap();
+    fn unsupported_path_aliases_are_ignored() {
+        let service = get_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .path_aliases(&[("cn", langid!("zh-Hans"))]);
 
-        let mut service = get_serv();
-        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+        assert_eq!(
+            service.lang_code_from_uri(&"/cn/lists".parse().unwrap()),
+            None
+        );
+    }
 
-        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+    #[tokio::test]
+    async fn strict_locale_segments_reject_unsupported_languages() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .strict_locale_segments();
 
-        service.rewrite_uri(&mut uri, &ident).unwrap();
+        for path in ["/xx/page", "/xx-YY/page", "/XX", "/xxx-Hant/page"] {
+            let req = http::Request::builder().uri(path).body(()).unwrap();
+            let response = service.call(req).await.unwrap();
 
-        assert_eq!("http://localhost:3000/lists", uri.to_string().as_str());
-    }
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        }
 
-    #[test]
-    fn can_rewrite_uri_same_starting_text() {
-        let mut uri = "http://localhost:3000/en/enrollment/details"
-            .parse::<Uri>()
-            .unwrap();
+        // Paths not starting with a language are still redirected
+        for (path, location) in [("/products/xx", "/en/products/xx"), ("/faq", "/en/faq")] {
+            let req = http::Request::builder().uri(path).body(()).unwrap();
+            let response = service.call(req).await.unwrap();
 
-        let mut service = get_serv();
-        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+            assert_eq!(response.status(), StatusCode::FOUND, "{path}");
+            assert_eq!(response.headers().get("Location").unwrap(), location);
+        }
 
-        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+        let req = http::Request::builder().uri("/ja/page").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        service.rewrite_uri(&mut uri, &ident).unwrap();
+        assert_eq!(body_string(response).await, "ja");
+    }
 
-        assert_eq!(
-            "http://localhost:3000/enrollment/details",
-            uri.to_string().as_str()
-        );
+    #[tokio::test]
+    async fn locale_shaped_segments_redirect_by_default() {
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
+
+        let req = http::Request::builder().uri("/xx/page").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/en/xx/page");
     }
 
-    #[test]
-    fn can_rewrite_uri_with_query_params() {
-        let mut uri = "http://localhost:3000/en/?page=1".parse::<Uri>().unwrap();
+    #[tokio::test]
+    async fn unsupported_default_language_does_not_redirect_twice() {
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
+        service.default_lang = langid!("fr");
 
-        let mut service = get_serv();
-        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
+        let req = http::Request::builder().uri("/page").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers().get("Location").unwrap(), "/fr/page");
 
-        service.rewrite_uri(&mut uri, &ident).unwrap();
+        // The second hit is passed through instead of redirecting to `/fr/fr/page`
+        let req = http::Request::builder().uri("/fr/page").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        assert_eq!("http://localhost:3000/?page=1", uri.to_string().as_str());
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_string(response).await, "fr");
     }
 
-    #[test]
-    fn can_redirect_with_query_params() {
-        let uri = "http://localhost:3000/?page=1".parse::<Uri>().unwrap();
+    #[tokio::test]
+    async fn redirect_body_links_to_target() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .redirect_body(
+                r#"<a href="{url}">Continue</a>"#,
+                HeaderValue::from_static("text/html; charset=utf-8"),
+            );
+
         let req = http::Request::builder()
-            .uri(uri)
-            .header("Accept-Language", "en-US,en;q=0.5")
+            .uri("/lists?a=1&b=2")
+            .header("Accept-Language", "ja")
             .body(())
             .unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let mut service = get_serv();
-        service.redirect_mode = RedirectMode::RedirectToLanguageSubPath;
-
-        let ident = LanguageIdentifier::from_str("en-US").unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers().get("Location").unwrap(),
+            "/ja/lists?a=1&b=2"
+        );
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(
+            body_string(response).await,
+            r#"<a href="/ja/lists?a=1&amp;b=2">Continue</a>"#
+        );
 
-        let (new_path, _) = service.build_redirect_path(&req);
+        let mut service =
+            service.redirect_body("Moved to {url}", HeaderValue::from_static("text/plain"));
+        let req = http::Request::builder()
+            .uri("/lists?a=1&b=2")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-        assert_eq!("/en/?page=1", new_path.as_str());
+        assert_eq!(body_string(response).await, "Moved to /en/lists?a=1&b=2");
     }
 
-    #[test]
-    fn can_get_supported_lang_code_from_uri() {
-        let uri = "http://localhost:3000/ja/lists".parse::<Uri>().unwrap();
+    #[tokio::test]
+    async fn redirect_body_is_empty_by_default() {
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
 
-        let service = get_serv();
+        let req = http::Request::builder().uri("/lists").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let ident = service.lang_code_from_uri(&uri);
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert!(response.headers().get(http::header::CONTENT_TYPE).is_none());
+        assert_eq!(body_string(response).await, "");
+    }
 
-        assert!(ident.is_some());
-        assert_eq!(ident.unwrap(), JAPANESE)
+    #[tokio::test]
+    async fn excluded_path_methods_only_exclude_their_method() {
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToLanguageSubPath)
+            .redirect_all_methods()
+            .excluded_paths(&["/health"])
+            .excluded_path_method("/webhooks", Method::POST);
+
+        let cases = [
+            (Method::POST, "/webhooks", StatusCode::OK),
+            (Method::POST, "/webhooks/github", StatusCode::OK),
+            (Method::GET, "/webhooks", StatusCode::FOUND),
+            (Method::PUT, "/webhooks", StatusCode::FOUND),
+            (Method::GET, "/health", StatusCode::OK),
+            (Method::POST, "/health", StatusCode::OK),
+            (Method::DELETE, "/health", StatusCode::OK),
+        ];
+        for (method, path, status) in cases {
+            let req = http::Request::builder()
+                .method(method.clone())
+                .uri(path)
+                .body(())
+                .unwrap();
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(response.status(), status, "{method} {path}");
+        }
     }
 
-    #[test]
-    fn unsupported_lang_code_from_uri() {
-        let uri = "http://localhost:3000/de/lists".parse::<Uri>().unwrap();
+    #[tokio::test]
+    async fn full_locale_redirect_collapses_to_supported_language() {
+        // The Accept media type parameter keeps the requested locale when its language is supported
+        let mut service = get_echo_serv()
+            .redirect(RedirectMode::RedirectToFullLocaleSubPath)
+            .accept_media_type_param("lang");
+        service.supported_langs = SupportedLanguages::new(&[ENGLISH, langid!("ja-JP")]);
 
-        let service = get_serv();
+        let req = http::Request::builder()
+            .uri("/lists")
+            .header("Accept", "text/html; lang=en-CA")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let ident = service.lang_code_from_uri(&uri);
+        assert_eq!(response.headers().get("Location").unwrap(), "/en-CA/lists");
 
-        assert!(ident.is_none());
-    }
+        let mut service = service.collapse_full_locale_redirect();
 
-    #[test]
-    fn can_extract_lang_header_single() {
-        let mut headers = HeaderMap::new();
-        headers.insert("Accept-Language", HeaderValue::from_static("en"));
+        for (accept, location) in [
+            ("text/html; lang=en-CA", "/en/lists"),
+            ("text/html; lang=ja-JP", "/ja-JP/lists"),
+        ] {
+            let req = http::Request::builder()
+                .uri("/lists")
+                .header("Accept", accept)
+                .body(())
+                .unwrap();
+            let response = service.call(req).await.unwrap();
 
-        let service = get_serv();
+            assert_eq!(response.status(), StatusCode::FOUND);
+            assert_eq!(response.headers().get("Location").unwrap(), location);
+        }
 
-        let ident = service.lang_code_from_headers(&headers).unwrap();
+        let req = http::Request::builder().uri("/en/lists").body(()).unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let target = "en".parse::<LanguageIdentifier>().unwrap();
-        assert_eq!(ident, target)
+        assert_eq!(body_string(response).await, "en");
     }
 
-    #[test]
-    fn can_extract_lang_header_compound() {
-        let mut headers = HeaderMap::new();
-        headers.insert("Accept-Language", HeaderValue::from_static("en-US"));
-
-        let service = get_serv();
+    #[tokio::test]
+    async fn can_read_language_from_accept_param() {
+        let mut service = get_echo_serv().accept_media_type_param("lang");
+
+        let cases = [
+            ("application/json; lang=ja", "ja"),
+            ("application/json;charset=utf-8;LANG=\"ja-JP\"", "ja-JP"),
+            ("text/html;lang=fr, application/json; lang=ja", "ja"),
+            ("application/json", "en"),
+        ];
+        for (accept, lang) in cases {
+            let req = http::Request::builder()
+                .header("Accept", accept)
+                .header("Accept-Language", "en")
+                .body(())
+                .unwrap();
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(body_string(response).await, lang, "{accept}");
+        }
 
-        let ident = service.lang_code_from_headers(&headers).unwrap();
+        // The parameter is ignored unless configured
+        let mut service = get_echo_serv();
+        let req = http::Request::builder()
+            .header("Accept", "application/json; lang=ja")
+            .body(())
+            .unwrap();
+        let response = service.call(req).await.unwrap();
 
-        let target = "en-US".parse::<LanguageIdentifier>().unwrap();
-        assert_eq!(ident, target)
+        assert_eq!(body_string(response).await, "en");
     }
 
-    #[test]
-    fn can_extract_lang_header_compound_with_quality_val() {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "Accept-Language",
-            HeaderValue::from_static("en-US,en;q=0.5"),
-        );
+    #[tokio::test]
+    async fn honors_upstream_locale() {
+        let mut service = get_echo_serv().redirect(RedirectMode::RedirectToLanguageSubPath);
+
+        for path in ["/lists", "/en/lists", "/lists?lang=en"] {
+            let mut req = http::Request::builder()
+                .uri(path)
+                .header("Accept-Language", "en")
+                .body(())
+                .unwrap();
+            req.extensions_mut().insert(JAPANESE);
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+            assert_eq!(body_string(response).await, "ja", "{path}");
+        }
 
-        let service = get_serv();
+        let mut extensions = http::Extensions::new();
+        assert_eq!(upstream_locale(&extensions), None);
 
-        let ident = service.lang_code_from_headers(&headers).unwrap();
+        extensions.insert(ResolvedLocale(JAPANESE));
+        assert_eq!(
+            upstream_locale(&extensions),
+            Some((JAPANESE, LocaleSource::Upstream))
+        );
 
-        let target = "en-US".parse::<LanguageIdentifier>().unwrap();
-        assert_eq!(ident.language, target.language)
+        extensions.insert(LocaleSource::Cookie);
+        assert_eq!(
+            upstream_locale(&extensions),
+            Some((JAPANESE, LocaleSource::Cookie))
+        );
     }
 
-    #[test]
-    fn can_extract_lang_header_wildcard() {
-        let mut headers = HeaderMap::new();
-        headers.insert("Accept-Language", HeaderValue::from_static("*"));
+    #[tokio::test]
+    async fn normalizes_locale_casing() {
+        assert_eq!(
+            "EN-us".parse::<LanguageIdentifier>().unwrap().to_string(),
+            "en-US"
+        );
+        assert_eq!(
+            "zh-hant-tw"
+                .parse::<LanguageIdentifier>()
+                .unwrap()
+                .to_string(),
+            "zh-Hant-TW"
+        );
+
+        let inner = tower::service_fn(|req: http::Request<()>| async move {
+            let lang = req
+                .extensions()
+                .get::<LanguageIdentifier>()
+                .map(|lang| lang.to_string())
+                .unwrap_or_default();
+
+            Ok::<_, Infallible>(axum::response::Response::new(axum::body::Body::from(
+                format!("{} {}", lang, req.uri()),
+            )))
+        });
+        let supported = [langid!("en-US"), JAPANESE];
+        let mut service = LanguageIdentifierExtractor::new(inner, &supported, &JAPANESE)
+            .redirect(RedirectMode::RedirectToFullLocaleSubPath);
+
+        for path in ["/EN-us/page", "/en-us/page", "/en-US/page"] {
+            let req = http::Request::builder().uri(path).body(()).unwrap();
+            let response = service.call(req).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+            assert_eq!(body_string(response).await, "en-US /page", "{path}");
+        }
     }
 }
@@ -0,0 +1,212 @@
+use fluent::FluentMessage;
+use fluent_syntax::ast;
+
+/// Serializes a message back to FTL, Ex. for review tools showing the source of a translation.
+///
+/// Bundles only keep the parsed messages, so the FTL is rebuilt from them in the canonical
+/// form: comments are dropped and multiline patterns are indented by four spaces.
+pub(crate) fn serialize_message(key: &str, message: &FluentMessage) -> String {
+    let mut ftl = String::from(key);
+    ftl.push_str(" =");
+
+    if let Some(value) = message.value() {
+        write_value(&mut ftl, &serialize_pattern(value), 4);
+    }
+    for attribute in message.attributes() {
+        ftl.push_str("\n    .");
+        ftl.push_str(attribute.id());
+        ftl.push_str(" =");
+        write_value(&mut ftl, &serialize_pattern(attribute.value()), 8);
+    }
+
+    ftl
+}
+
+/// Serializes an attribute of a message back to FTL, as `.attribute = value`
+pub(crate) fn serialize_attribute(id: &str, value: &ast::Pattern<&str>) -> String {
+    let mut ftl = format!(".{id} =");
+    write_value(&mut ftl, &serialize_pattern(value), 4);
+
+    ftl
+}
+
+/// Writes a value after its `=`, starting multiline values on their own indented lines
+fn write_value(ftl: &mut String, value: &str, indent: usize) {
+    if value.contains('\n') {
+        ftl.push('\n');
+        ftl.push_str(&indent_lines(value, indent, true));
+    } else {
+        ftl.push(' ');
+        ftl.push_str(value);
+    }
+}
+
+/// Indents the lines of a value, leaving empty lines without trailing spaces
+fn indent_lines(value: &str, indent: usize, first_line: bool) -> String {
+    let padding = " ".repeat(indent);
+
+    value
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if line.is_empty() || (i == 0 && !first_line) {
+                line.to_string()
+            } else {
+                format!("{padding}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn serialize_pattern(pattern: &ast::Pattern<&str>) -> String {
+    pattern
+        .elements
+        .iter()
+        .map(|element| match element {
+            ast::PatternElement::TextElement { value } => value.to_string(),
+            ast::PatternElement::Placeable { expression } => serialize_placeable(expression),
+        })
+        .collect()
+}
+
+fn serialize_placeable(expression: &ast::Expression<&str>) -> String {
+    match expression {
+        ast::Expression::Inline(expression) => {
+            format!("{{ {} }}", serialize_inline(expression))
+        }
+        ast::Expression::Select { selector, variants } => {
+            let variants = variants
+                .iter()
+                .map(|variant| {
+                    let key = match &variant.key {
+                        ast::VariantKey::Identifier { name } => name,
+                        ast::VariantKey::NumberLiteral { value } => value,
+                    };
+                    let marker = if variant.default { "   *" } else { "    " };
+                    let value = serialize_pattern(&variant.value);
+
+                    format!("{marker}[{key}] {}", indent_lines(&value, 8, false))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            format!("{{ {} ->\n{variants}\n}}", serialize_inline(selector))
+        }
+    }
+}
+
+fn serialize_inline(expression: &ast::InlineExpression<&str>) -> String {
+    match expression {
+        ast::InlineExpression::StringLiteral { value } => format!("\"{value}\""),
+        ast::InlineExpression::NumberLiteral { value } => value.to_string(),
+        ast::InlineExpression::FunctionReference { id, arguments } => {
+            format!("{}{}", id.name, serialize_arguments(arguments))
+        }
+        ast::InlineExpression::MessageReference { id, attribute } => match attribute {
+            Some(attribute) => format!("{}.{}", id.name, attribute.name),
+            None => id.name.to_string(),
+        },
+        ast::InlineExpression::TermReference {
+            id,
+            attribute,
+            arguments,
+        } => {
+            let mut term = format!("-{}", id.name);
+            if let Some(attribute) = attribute {
+                term.push('.');
+                term.push_str(attribute.name);
+            }
+            if let Some(arguments) = arguments {
+                term.push_str(&serialize_arguments(arguments));
+            }
+
+            term
+        }
+        ast::InlineExpression::VariableReference { id } => format!("${}", id.name),
+        ast::InlineExpression::Placeable { expression } => serialize_placeable(expression),
+    }
+}
+
+fn serialize_arguments(arguments: &ast::CallArguments<&str>) -> String {
+    let positional = arguments.positional.iter().map(serialize_inline);
+    let named = arguments.named.iter().map(|argument| {
+        format!(
+            "{}: {}",
+            argument.name.name,
+            serialize_inline(&argument.value)
+        )
+    });
+
+    format!(
+        "({})",
+        positional.chain(named).collect::<Vec<_>>().join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent::{FluentBundle, FluentResource};
+    use fluent_syntax::parser::parse;
+
+    use super::*;
+
+    /// Serializes the only message of the FTL
+    fn reserialize(ftl: &str) -> String {
+        let resource = FluentResource::try_new(ftl.to_string()).unwrap();
+        let mut bundle = FluentBundle::default();
+        bundle.add_resource(resource).unwrap();
+
+        serialize_message("key", &bundle.get_message("key").unwrap())
+    }
+
+    #[test]
+    fn serializes_simple_messages() {
+        assert_eq!(reserialize("key = Hello World"), "key = Hello World");
+        assert_eq!(
+            reserialize("key = Hello { $name }, welcome to { -brand-name }"),
+            "key = Hello { $name }, welcome to { -brand-name }"
+        );
+        assert_eq!(
+            reserialize(
+                "key = { NUMBER($count, minimumFractionDigits: 2) } { other.title } { \"{\" }"
+            ),
+            "key = { NUMBER($count, minimumFractionDigits: 2) } { other.title } { \"{\" }"
+        );
+    }
+
+    #[test]
+    fn serializes_attributes_and_multiline_patterns() {
+        let ftl = "key = Hello\n    there\n    .title = Greeting\n";
+
+        assert_eq!(
+            reserialize(ftl),
+            "key =\n    Hello\n    there\n    .title = Greeting"
+        );
+        assert_eq!(
+            reserialize("key =\n    .title = Greeting"),
+            "key =\n    .title = Greeting"
+        );
+    }
+
+    #[test]
+    fn serializes_select_expressions() {
+        let ftl = "key = { $count ->\n    [one] One item\n   *[other] { $count } items\n}\n";
+
+        assert_eq!(
+            reserialize(ftl),
+            "key =\n    { $count ->\n        [one] One item\n       *[other] { $count } items\n    }"
+        );
+    }
+
+    #[test]
+    fn serialized_messages_parse_to_same_ast() {
+        let ftl = "key = Hi { $name }\n    second { $n ->\n        [0] none\n       *[other] { $n ->\n            [one] single\n           *[many] lines\n                more\n        }\n    }\n    .attr = { -term(case: \"gen\") }\n";
+        let serialized = reserialize(ftl);
+
+        let original = parse(ftl).unwrap();
+        let reparsed = parse(serialized.as_str()).unwrap();
+
+        assert_eq!(original.body, reparsed.body, "{serialized}");
+    }
+}
@@ -0,0 +1,226 @@
+use std::{collections::HashSet, sync::Arc};
+
+use unic_langid::{
+    subtags::{Language, Script},
+    LanguageIdentifier,
+};
+
+use crate::MatchStrictness;
+
+/// The supported languages of the service, indexed for constant time lookups
+/// on every request.
+///
+/// Cloning only clones an `Arc`, as the service is cloned for each request.
+#[derive(Debug, Clone)]
+pub(crate) struct SupportedLanguages(Arc<Index>);
+
+#[derive(Debug)]
+struct Index {
+    langs: Vec<LanguageIdentifier>,
+    locales: HashSet<LanguageIdentifier>,
+    languages: HashSet<Language>,
+    scripts: HashSet<(Language, Option<Script>)>,
+}
+
+impl SupportedLanguages {
+    pub(crate) fn new(langs: &[LanguageIdentifier]) -> Self {
+        Self(Arc::new(Index {
+            langs: langs.to_vec(),
+            locales: langs.iter().cloned().collect(),
+            languages: langs.iter().map(|lang| lang.language).collect(),
+            scripts: langs
+                .iter()
+                .map(|lang| (lang.language, lang.script))
+                .collect(),
+        }))
+    }
+
+    /// The supported languages in the order they were given
+    pub(crate) fn as_slice(&self) -> &[LanguageIdentifier] {
+        &self.0.langs
+    }
+
+    /// Negotiates the best supported language for the requested languages, in order of
+    /// preference
+    #[cfg(feature = "negotiation")]
+    pub(crate) fn negotiate(
+        &self,
+        requested: &[LanguageIdentifier],
+        strategy: fluent_langneg::NegotiationStrategy,
+    ) -> Option<LanguageIdentifier> {
+        fluent_langneg::negotiate::filter_matches(requested, &self.0.langs, strategy)
+            .first()
+            .map(|ident| (*ident).clone())
+    }
+
+    /// Checks a requested language identifier matches one of the supported languages
+    pub(crate) fn contains(
+        &self,
+        requested: &LanguageIdentifier,
+        strictness: MatchStrictness,
+    ) -> bool {
+        match strictness {
+            MatchStrictness::Language => self.0.languages.contains(&requested.language),
+            MatchStrictness::LanguageScript => match requested.script {
+                Some(script) => self.0.scripts.contains(&(requested.language, Some(script))),
+                None => self.0.languages.contains(&requested.language),
+            },
+            MatchStrictness::FullLocale => self.0.locales.contains(requested),
+        }
+    }
+
+    /// Finds the most specific supported language identifier matching a requested one, trying
+    /// the full locale, then the language and script, then the language and region, then the
+    /// language, as far as the strictness allows.
+    ///
+    /// Bare identifiers are preferred at each step, Ex. `zh-Hant` over `zh-Hant-HK` for
+    /// `zh-Hant-TW`, then the supported languages in the order they were given.
+    pub(crate) fn best_match(
+        &self,
+        requested: &LanguageIdentifier,
+        strictness: MatchStrictness,
+    ) -> Option<LanguageIdentifier> {
+        if let Some(ident) = self.0.locales.get(requested) {
+            return Some(ident.clone());
+        }
+        if strictness == MatchStrictness::FullLocale {
+            return None;
+        }
+
+        if let Some(script) = requested.script {
+            let matched = self.find_variant(
+                LanguageIdentifier::from_parts(requested.language, Some(script), None, &[]),
+                |ident| ident.language == requested.language && ident.script == Some(script),
+            );
+            if matched.is_some() || strictness == MatchStrictness::LanguageScript {
+                return matched;
+            }
+        }
+
+        if let Some(region) = requested.region {
+            let matched = self.find_variant(
+                LanguageIdentifier::from_parts(requested.language, None, Some(region), &[]),
+                |ident| ident.language == requested.language && ident.region == Some(region),
+            );
+            if matched.is_some() {
+                return matched;
+            }
+        }
+
+        self.find_variant(
+            LanguageIdentifier::from_parts(requested.language, None, None, &[]),
+            |ident| ident.language == requested.language,
+        )
+    }
+
+    /// Returns the bare identifier if supported, or the first supported one matching
+    fn find_variant(
+        &self,
+        bare: LanguageIdentifier,
+        matches: impl Fn(&LanguageIdentifier) -> bool,
+    ) -> Option<LanguageIdentifier> {
+        self.0
+            .locales
+            .get(&bare)
+            .or_else(|| self.0.langs.iter().find(|ident| matches(ident)))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use unic_langid::langid;
+
+    use super::*;
+
+    /// The linear search used before indexing the supported languages
+    fn linear_contains(
+        supported: &[LanguageIdentifier],
+        requested: &LanguageIdentifier,
+        strictness: MatchStrictness,
+    ) -> bool {
+        supported.iter().any(|ident| match strictness {
+            MatchStrictness::Language => ident.language == requested.language,
+            MatchStrictness::LanguageScript => {
+                ident.language == requested.language
+                    && (requested.script.is_none() || ident.script == requested.script)
+            }
+            MatchStrictness::FullLocale => ident == requested,
+        })
+    }
+
+    #[test]
+    fn best_match_prefers_most_specific_variant() {
+        let index = SupportedLanguages::new(&[
+            langid!("en-US"),
+            langid!("zh-Hant"),
+            langid!("zh-Hans"),
+            langid!("zh-TW"),
+            langid!("sr-Latn-RS"),
+        ]);
+
+        for (requested, strictness, target) in [
+            ("zh-Hant-TW", MatchStrictness::Language, Some("zh-Hant")),
+            ("zh-Hans-CN", MatchStrictness::Language, Some("zh-Hans")),
+            ("zh-TW", MatchStrictness::Language, Some("zh-TW")),
+            ("zh-HK", MatchStrictness::Language, Some("zh-Hant")),
+            ("zh", MatchStrictness::Language, Some("zh-Hant")),
+            ("en-GB", MatchStrictness::Language, Some("en-US")),
+            ("sr-Latn", MatchStrictness::Language, Some("sr-Latn-RS")),
+            ("sr-Cyrl", MatchStrictness::Language, Some("sr-Latn-RS")),
+            ("sr-Cyrl", MatchStrictness::LanguageScript, None),
+            ("zh-Hant-TW", MatchStrictness::FullLocale, None),
+            ("zh-TW", MatchStrictness::FullLocale, Some("zh-TW")),
+            ("de", MatchStrictness::Language, None),
+        ] {
+            let requested = requested.parse::<LanguageIdentifier>().unwrap();
+            let target = target.map(|tag| tag.parse::<LanguageIdentifier>().unwrap());
+
+            assert_eq!(
+                index.best_match(&requested, strictness),
+                target,
+                "{requested} with {strictness:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_linear_search() {
+        let supported = vec![
+            langid!("en"),
+            langid!("en-US"),
+            langid!("ja"),
+            langid!("zh-Hans"),
+            langid!("zh-Hant-TW"),
+            langid!("sr-Latn"),
+        ];
+        let requested = [
+            langid!("en"),
+            langid!("en-GB"),
+            langid!("en-Latn"),
+            langid!("ja-JP"),
+            langid!("zh"),
+            langid!("zh-Hans"),
+            langid!("zh-Hant"),
+            langid!("zh-Hant-TW"),
+            langid!("sr"),
+            langid!("sr-Cyrl"),
+            langid!("de"),
+        ];
+        let index = SupportedLanguages::new(&supported);
+
+        for strictness in [
+            MatchStrictness::Language,
+            MatchStrictness::LanguageScript,
+            MatchStrictness::FullLocale,
+        ] {
+            for ident in &requested {
+                assert_eq!(
+                    index.contains(ident, strictness),
+                    linear_contains(&supported, ident, strictness),
+                    "{ident} with {strictness:?}"
+                );
+            }
+        }
+    }
+}
@@ -1,35 +1,546 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
 
-use crate::{fluent::MessageAttribute, Localizer};
-use fluent::{
-    types::{FluentNumber, FluentNumberOptions},
-    FluentArgs, FluentValue,
+use crate::{
+    fluent::{Bundle, MessageAttribute},
+    fluent_args_from_json,
+    json::NUMBER_OPTION_KEYS,
+    localized_path, number_options_from_json, prefixed_path, text_direction, Localizer, MessageKey,
+    RedirectMode,
 };
-use serde_json::Value;
+use fluent::FluentArgs;
+use fluent_syntax::ast;
 use unic_langid::LanguageIdentifier;
 
+/// Names of the reserved parameters read by the tera function and filter.
+///
+/// Rename them if they collide with the variables used in your FTL messages.
+///
+/// # Example
+/// ```ignore
+/// let localizer = Localizer::new().set_tera_config(
+///     LocalizerTeraConfig::new().lang_param("locale").key_param("id"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalizerTeraConfig {
+    lang: String,
+    key: String,
+    attribute: String,
+    args: String,
+    missing_key: MissingKey,
+}
+
+/// What the tera function and filter render when a message cannot be formatted, Ex. when its
+/// key is missing from the bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingKey {
+    /// Returns a `tera::Error`, which aborts rendering
+    #[default]
+    Error,
+    /// Renders the key, Ex. `[missing: welcome-message]`, to spot missing translations
+    EchoKey,
+    /// Renders an empty string
+    Empty,
+}
+
+impl Default for LocalizerTeraConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalizerTeraConfig {
+    pub fn new() -> Self {
+        Self {
+            lang: String::from("lang"),
+            key: String::from("key"),
+            attribute: String::from("attribute"),
+            args: String::from("args"),
+            missing_key: MissingKey::Error,
+        }
+    }
+
+    /// Set the name of the language identifier parameter. Defaults to `lang`.
+    pub fn lang_param(mut self, name: &str) -> Self {
+        self.lang = name.to_string();
+
+        self
+    }
+
+    /// Set the name of the FTL key parameter. Defaults to `key`.
+    pub fn key_param(mut self, name: &str) -> Self {
+        self.key = name.to_string();
+
+        self
+    }
+
+    /// Set the name of the FTL attribute parameter. Defaults to `attribute`.
+    pub fn attribute_param(mut self, name: &str) -> Self {
+        self.attribute = name.to_string();
+
+        self
+    }
+
+    /// Set the name of the FTL arguments parameter. Defaults to `args`.
+    ///
+    /// The entries of this object parameter are passed as fluent arguments, taking precedence
+    /// over the other parameters, so variables may share the name of a reserved parameter.
+    pub fn args_param(mut self, name: &str) -> Self {
+        self.args = name.to_string();
+
+        self
+    }
+
+    /// Set what is rendered when a message cannot be formatted. Defaults to
+    /// `MissingKey::Error`.
+    ///
+    /// Invalid parameters, such as a missing `lang`, are still errors.
+    pub fn missing_key(mut self, missing_key: MissingKey) -> Self {
+        self.missing_key = missing_key;
+
+        self
+    }
+}
+
 impl tera::Function for Localizer {
     fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
-        let lang_arg = args
-            .get("lang")
-            .and_then(|lang| lang.as_str())
-            .and_then(|str| str.parse::<LanguageIdentifier>().ok())
-            .ok_or(tera::Error::msg("missing lang param"))?;
+        let ftl_key = args
+            .get(&self.tera_config().key)
+            .and_then(|key| key.as_str())
+            .ok_or(tera::Error::msg("missing ftl key"))?;
+
+        self.format_tera_message(ftl_key, args, None)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Use the piped value as the FTL key.
+///
+/// # Example
+/// ```html
+/// <h1>{{ "welcome-message" | l10n(lang=lang) }}</h1>
+/// ```
+impl tera::Filter for Localizer {
+    fn filter(
+        &self,
+        value: &serde_json::Value,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> tera::Result<serde_json::Value> {
+        let ftl_key = value
+            .as_str()
+            .ok_or(tera::Error::msg("ftl key must be a string"))?;
+
+        self.format_tera_message(ftl_key, args, None)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A shared [Localizer], to use the same instance in tera and in handlers without cloning
+/// its bundles.
+///
+/// `tera::Function` cannot be implemented on `Arc<Localizer>` directly, so wrap it instead.
+///
+/// # Example
+/// ```ignore
+/// let localizer = Arc::new(localizer);
+///
+/// tera.register_function("fluent", SharedLocalizer::from(localizer.clone()));
+/// tera.register_filter("l10n", SharedLocalizer::from(localizer.clone()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedLocalizer(pub Arc<Localizer>);
+
+impl From<Arc<Localizer>> for SharedLocalizer {
+    fn from(localizer: Arc<Localizer>) -> Self {
+        Self(localizer)
+    }
+}
+
+impl tera::Function for SharedLocalizer {
+    fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
+        tera::Function::call(self.0.as_ref(), args)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+impl tera::Filter for SharedLocalizer {
+    fn filter(
+        &self,
+        value: &serde_json::Value,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> tera::Result<serde_json::Value> {
+        tera::Filter::filter(self.0.as_ref(), value, args)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A shared [Localizer] bound to a locale, so that templates may omit the `lang` parameter.
+///
+/// Created with [Localizer::for_locale]. Cloning only clones the `Arc` and the locale.
+///
+/// # Example
+/// ```ignore
+/// async fn lists_view(
+///     State(state): State<ViewRouterState>,
+///     Extension(lang): Extension<LanguageIdentifier>,
+/// ) -> axum::response::Response {
+///     let mut tera = state.tera.as_ref().clone();
+///     tera.register_function("fluent", state.localizer.for_locale(lang));
+///
+///     // {{ fluent(key="list-family") }}
+///     let html = tera.render("lists.html", &Context::new()).unwrap();
+///
+///     Html(html).into_response()
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BoundLocalizer {
+    localizer: Arc<Localizer>,
+    locale: LanguageIdentifier,
+}
+
+impl BoundLocalizer {
+    pub fn locale(&self) -> &LanguageIdentifier {
+        &self.locale
+    }
+
+    /// Format a FTL message into the bound locale if available.
+    pub fn format_message(
+        &self,
+        key: &(impl MessageKey + ?Sized),
+        args: Option<&FluentArgs>,
+    ) -> Option<String> {
+        self.localizer.format_message(&self.locale, key, args)
+    }
+}
 
+impl tera::Function for BoundLocalizer {
+    fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
         let ftl_key = args
-            .get("key")
+            .get(&self.localizer.tera_config().key)
             .and_then(|key| key.as_str())
             .ok_or(tera::Error::msg("missing ftl key"))?;
 
-        let ftl_attribute = args.get("attribute").and_then(|attr| attr.as_str());
+        self.localizer
+            .format_tera_message(ftl_key, args, Some(&self.locale))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+impl tera::Filter for BoundLocalizer {
+    fn filter(
+        &self,
+        value: &serde_json::Value,
+        args: &HashMap<String, serde_json::Value>,
+    ) -> tera::Result<serde_json::Value> {
+        let ftl_key = value
+            .as_str()
+            .ok_or(tera::Error::msg("ftl key must be a string"))?;
+
+        self.localizer
+            .format_tera_message(ftl_key, args, Some(&self.locale))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A tera function returning the text direction of the `lang` parameter, `ltr` or `rtl`.
+///
+/// # Example
+/// ```ignore
+/// tera.register_function("locale_dir", LocaleDirection);
+/// ```
+///
+/// ```html
+/// <html lang="{{ lang }}" dir="{{ locale_dir(lang=lang) }}">
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocaleDirection;
 
-        let fluent_args: FluentArgs = args
+impl tera::Function for LocaleDirection {
+    fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
+        let lang = args
+            .get("lang")
+            .ok_or(tera::Error::msg("missing lang param"))?
+            .as_str()
+            .and_then(|str| str.parse::<LanguageIdentifier>().ok())
+            .ok_or(tera::Error::msg("invalid lang param"))?;
+
+        Ok(serde_json::Value::String(
+            text_direction(&lang).as_str().to_string(),
+        ))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A tera function formatting the `timestamp` parameter, in unix seconds or as an RFC 3339
+/// string, with the conventions of the `lang` parameter, see [Localizer::format_datetime].
+///
+/// The optional `style` parameter is `date` (the default), `time` or `datetime`.
+///
+/// # Example
+/// ```ignore
+/// tera.register_function("format_datetime", FormatDatetime);
+/// ```
+///
+/// ```html
+/// <time>{{ format_datetime(timestamp=post.created_at, lang=lang, style="datetime") }}</time>
+/// ```
+#[cfg(feature = "datetime")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatDatetime;
+
+#[cfg(feature = "datetime")]
+impl tera::Function for FormatDatetime {
+    fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
+        let lang = args
+            .get("lang")
+            .ok_or(tera::Error::msg("missing lang param"))?
+            .as_str()
+            .and_then(|str| str.parse::<LanguageIdentifier>().ok())
+            .ok_or(tera::Error::msg("invalid lang param"))?;
+        let timestamp = match args.get("timestamp") {
+            Some(serde_json::Value::Number(secs)) => secs
+                .as_i64()
+                .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                .map(|timestamp| timestamp.fixed_offset()),
+            Some(serde_json::Value::String(timestamp)) => {
+                chrono::DateTime::parse_from_rfc3339(timestamp).ok()
+            }
+            Some(_) => None,
+            None => return Err(tera::Error::msg("missing timestamp param")),
+        }
+        .ok_or(tera::Error::msg("invalid timestamp param"))?;
+        let style = match args.get("style") {
+            Some(style) => style
+                .as_str()
+                .and_then(crate::DateTimeStyle::from_name)
+                .ok_or(tera::Error::msg("invalid style param"))?,
+            None => crate::DateTimeStyle::Date,
+        };
+
+        Ok(serde_json::Value::String(crate::datetime::format_datetime(
+            &lang, &timestamp, style,
+        )))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A tera function prefixing the `path` parameter with the language sub-path of the `lang`
+/// parameter, see [crate::localized_path].
+///
+/// # Example
+/// ```ignore
+/// tera.register_function(
+///     "localized_path",
+///     LocalizedPath(RedirectMode::RedirectToLanguageSubPath),
+/// );
+/// ```
+///
+/// ```html
+/// <a href="{{ localized_path(lang="ja", path="/products/42") }}">日本語</a>
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalizedPath(pub RedirectMode);
+
+impl tera::Function for LocalizedPath {
+    fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
+        let lang = args
+            .get("lang")
+            .ok_or(tera::Error::msg("missing lang param"))?
+            .as_str()
+            .and_then(|str| str.parse::<LanguageIdentifier>().ok())
+            .ok_or(tera::Error::msg("invalid lang param"))?;
+        let path = args
+            .get("path")
+            .and_then(|path| path.as_str())
+            .ok_or(tera::Error::msg("missing path param"))?;
+
+        Ok(serde_json::Value::String(localized_path(
+            &lang, path, &self.0,
+        )))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// A tera function listing the alternate links of the `path` parameter for every supported
+/// language, as objects with `hreflang` and `href` fields.
+///
+/// Created from the layer with
+/// [crate::LanguageIdentifierExtractorLayer::hreflang_alternates], so that the supported
+/// languages are not duplicated. The path is the request path without its language segment.
+///
+/// # Example
+/// ```ignore
+/// tera.register_function("alternates", layer.hreflang_alternates());
+/// ```
+///
+/// ```html
+/// {% for alternate in alternates(path=path) %}
+/// <link rel="alternate" hreflang="{{ alternate.hreflang }}" href="{{ alternate.href }}">
+/// {% endfor %}
+/// ```
+#[derive(Debug, Clone)]
+pub struct HreflangAlternates {
+    supported_langs: Vec<LanguageIdentifier>,
+    redirect_mode: RedirectMode,
+    default_lang: Option<LanguageIdentifier>,
+}
+
+impl HreflangAlternates {
+    pub fn new(supported_langs: Vec<LanguageIdentifier>, redirect_mode: RedirectMode) -> Self {
+        Self {
+            supported_langs,
+            redirect_mode,
+            default_lang: None,
+        }
+    }
+
+    /// Set the default language, left unprefixed with `RedirectMode::PrefixNonDefaultOnly`
+    pub fn default_language(mut self, default_lang: LanguageIdentifier) -> Self {
+        self.default_lang = Some(default_lang);
+
+        self
+    }
+}
+
+impl tera::Function for HreflangAlternates {
+    fn call(&self, args: &HashMap<String, serde_json::Value>) -> tera::Result<serde_json::Value> {
+        let path = args
+            .get("path")
+            .and_then(|path| path.as_str())
+            .ok_or(tera::Error::msg("missing path param"))?;
+
+        let alternates = self
+            .supported_langs
             .iter()
-            .filter(|(key, _)| key.as_str() != "key")
-            .map(|(key, val)| (key, json_value_to_fluent_value(val, self.number_options())))
+            .map(|lang| {
+                serde_json::json!({
+                    "hreflang": lang.to_string(),
+                    "href": prefixed_path(
+                        lang,
+                        path,
+                        &self.redirect_mode,
+                        self.default_lang.as_ref(),
+                    ),
+                })
+            })
             .collect();
 
-        let message = if let Some(ftl_attribute) = ftl_attribute {
+        Ok(serde_json::Value::Array(alternates))
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+impl Localizer {
+    /// Binds a shared localizer to a locale, to be registered as a tera function or filter
+    /// that uses the locale when the `lang` parameter is omitted.
+    pub fn for_locale(self: &Arc<Self>, locale: LanguageIdentifier) -> BoundLocalizer {
+        BoundLocalizer {
+            localizer: self.clone(),
+            locale,
+        }
+    }
+
+    fn format_tera_message(
+        &self,
+        ftl_key: &str,
+        args: &HashMap<String, serde_json::Value>,
+        default_lang: Option<&LanguageIdentifier>,
+    ) -> tera::Result<serde_json::Value> {
+        let config = self.tera_config();
+
+        let lang_arg = match args.get(&config.lang) {
+            Some(lang) => lang
+                .as_str()
+                .and_then(|str| str.parse::<LanguageIdentifier>().ok())
+                .ok_or(tera::Error::msg("invalid lang param"))?,
+            None => default_lang
+                .cloned()
+                .ok_or(tera::Error::msg("missing lang param"))?,
+        };
+
+        let ftl_attribute = args.get(&config.attribute).and_then(|attr| attr.as_str());
+
+        // Parameters named like number options are still variables of messages using them,
+        // Ex. `currency` for `price = { $amount } { $currency }`
+        let variables = self
+            .message_bundle(&lang_arg, ftl_key)
+            .map(|bundle| message_variables(bundle, ftl_key))
+            .unwrap_or_default();
+        let number_keys = NUMBER_OPTION_KEYS
+            .iter()
+            .copied()
+            .filter(|key| args.contains_key(*key) && !variables.contains(*key))
+            .collect::<Vec<_>>();
+
+        let number_opts = if number_keys.is_empty() {
+            Cow::Borrowed(self.number_options())
+        } else {
+            Cow::Owned(number_options_from_json(
+                args.iter()
+                    .filter(|(key, _)| number_keys.contains(&key.as_str())),
+                self.number_options(),
+            ))
+        };
+
+        let reserved = [config.key.as_str(), config.args.as_str()]
+            .into_iter()
+            .chain(number_keys)
+            .collect::<Vec<_>>();
+
+        let mut fluent_args =
+            fluent_args_from_json(args, &number_opts, self.list_separator(), &reserved)
+                .map_err(|err| tera::Error::chain("invalid fluent argument", err))?;
+
+        match args.get(&config.args) {
+            Some(serde_json::Value::Object(nested)) => {
+                let nested_args =
+                    fluent_args_from_json(nested, &number_opts, self.list_separator(), &[])
+                        .map_err(|err| tera::Error::chain("invalid fluent argument", err))?;
+
+                for (key, value) in nested_args {
+                    fluent_args.set(key, value);
+                }
+            }
+            Some(_) => return Err(tera::Error::msg("args param must be an object")),
+            None => {}
+        }
+
+        let result = if let Some(ftl_attribute) = ftl_attribute {
             self.format_message_result(
                 &lang_arg,
                 &MessageAttribute {
@@ -40,48 +551,482 @@ impl tera::Function for Localizer {
             )
         } else {
             self.format_message_result(&lang_arg, ftl_key, Some(&fluent_args))
-        }
-        .map_err(|err| tera::Error::chain("failed to format message", err))?;
+        };
+
+        let message = match (result, config.missing_key) {
+            (Ok(message), _) => message,
+            (Err(err), MissingKey::Error) => {
+                return Err(tera::Error::chain("failed to format message", err))
+            }
+            (Err(_), MissingKey::EchoKey) => match ftl_attribute {
+                Some(ftl_attribute) => format!("[missing: {ftl_key}.{ftl_attribute}]"),
+                None => format!("[missing: {ftl_key}]"),
+            },
+            (Err(_), MissingKey::Empty) => String::new(),
+        };
 
         Ok(serde_json::Value::String(message))
     }
+}
 
-    fn is_safe(&self) -> bool {
-        true
+/// Lists the variables referenced by a message, its attributes and the messages they reference,
+/// Ex. `name` for `hello = Hi { $name }`
+fn message_variables(bundle: &Bundle, key: &str) -> BTreeSet<String> {
+    let mut variables = BTreeSet::new();
+    collect_inline_variables(
+        bundle,
+        &ast::InlineExpression::MessageReference {
+            id: ast::Identifier { name: key },
+            attribute: None,
+        },
+        &mut variables,
+        &mut BTreeSet::new(),
+    );
+
+    variables
+}
+
+/// Collects the variables referenced by a pattern, following the messages it references, as
+/// they are formatted with the same arguments
+fn collect_variables(
+    bundle: &Bundle,
+    pattern: &ast::Pattern<&str>,
+    variables: &mut BTreeSet<String>,
+    visited: &mut BTreeSet<String>,
+) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            collect_expression_variables(bundle, expression, variables, visited);
+        }
     }
 }
 
-fn json_value_to_fluent_value<'a>(
-    json_value: &'a serde_json::Value,
-    number_opts: &FluentNumberOptions,
-) -> fluent::FluentValue<'a> {
-    match json_value {
-        Value::Number(n) => n
-            .as_f64()
-            .map(|n_f64| {
-                let f_n = FluentNumber::new(n_f64, number_opts.clone());
-                FluentValue::Number(f_n)
-            })
-            .unwrap_or_else(|| FluentValue::from(n.to_string())),
-        Value::String(s) => FluentValue::String(Cow::Borrowed(s)),
-        Value::Null => FluentValue::None,
-        _ => FluentValue::from(json_value.to_string()),
+fn collect_expression_variables(
+    bundle: &Bundle,
+    expression: &ast::Expression<&str>,
+    variables: &mut BTreeSet<String>,
+    visited: &mut BTreeSet<String>,
+) {
+    match expression {
+        ast::Expression::Inline(expression) => {
+            collect_inline_variables(bundle, expression, variables, visited)
+        }
+        ast::Expression::Select { selector, variants } => {
+            collect_inline_variables(bundle, selector, variables, visited);
+            for variant in variants {
+                collect_variables(bundle, &variant.value, variables, visited);
+            }
+        }
+    }
+}
+
+fn collect_inline_variables(
+    bundle: &Bundle,
+    expression: &ast::InlineExpression<&str>,
+    variables: &mut BTreeSet<String>,
+    visited: &mut BTreeSet<String>,
+) {
+    match expression {
+        ast::InlineExpression::VariableReference { id } => {
+            variables.insert(id.name.to_string());
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => {
+            let named = arguments.named.iter().map(|argument| &argument.value);
+            for argument in arguments.positional.iter().chain(named) {
+                collect_inline_variables(bundle, argument, variables, visited);
+            }
+        }
+        ast::InlineExpression::MessageReference { id, .. } => {
+            if !visited.insert(id.name.to_string()) {
+                return;
+            }
+            if let Some(message) = bundle.get_message(id.name) {
+                let attributes = message.attributes().map(|attribute| attribute.value());
+                for pattern in message.value().into_iter().chain(attributes) {
+                    collect_variables(bundle, pattern, variables, visited);
+                }
+            }
+        }
+        ast::InlineExpression::Placeable { expression } => {
+            collect_expression_variables(bundle, expression, variables, visited)
+        }
+        // Terms are only formatted with their own arguments, which are literals
+        ast::InlineExpression::TermReference { .. }
+        | ast::InlineExpression::StringLiteral { .. }
+        | ast::InlineExpression::NumberLiteral { .. } => {}
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use fluent::{types::FluentNumberOptions, FluentValue};
+    use tera::{Context, Tera};
+    use unic_langid::langid;
+
     use super::*;
 
+    fn get_localizer() -> Localizer {
+        let mut loc = Localizer::new();
+        loc.add_bundle(langid!("en"), &["test_data/main.ftl", "test_data/sub.ftl"])
+            .unwrap();
+
+        loc
+    }
+
+    fn render(tera: &mut Tera, template: &str) -> tera::Result<String> {
+        tera.add_raw_template("test", template)?;
+
+        let mut ctx = Context::new();
+        ctx.insert("lang", "en");
+        ctx.insert("key", "test-name");
+
+        tera.render("test", &ctx)
+    }
+
+    #[test]
+    fn number_options_match_format_message() {
+        let loc = get_localizer().set_fluent_number_options(FluentNumberOptions {
+            minimum_fraction_digits: Some(2),
+            ..Default::default()
+        });
+
+        let mut args = FluentArgs::new();
+        args.set("number", 5);
+        let message = loc.format_message_with_numbers(&langid!("en"), "test-key-b", Some(&args));
+
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc);
+
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="test-key-b", lang=lang, number=5) }}"#,
+        )
+        .unwrap();
+
+        assert_eq!(message.as_deref(), Some(html.as_str()));
+        assert_eq!("\u{2068}5.00\u{2069} new messages", html);
+    }
+
     #[test]
-    fn can_convert_num_to_fluent_num() {
-        let num = serde_json::Value::from(2);
+    fn can_render_function() {
+        let mut tera = Tera::default();
+        tera.register_function("fluent", get_localizer());
+
+        let html = render(&mut tera, r#"{{ fluent(key="test-key-a", lang=lang) }}"#).unwrap();
+
+        assert_eq!("Hello World", html);
+    }
+
+    #[test]
+    fn can_render_filter() {
+        let mut tera = Tera::default();
+        tera.register_filter("l10n", get_localizer());
+
+        let html = render(&mut tera, r#"{{ key | l10n(lang=lang, name="Bob") }}"#).unwrap();
+
+        assert_eq!("Peg \u{2068}Bob\u{2069}", html);
+    }
+
+    #[test]
+    fn can_render_filter_attribute() {
+        let mut tera = Tera::default();
+        tera.register_filter("l10n", get_localizer());
+
+        let html = render(
+            &mut tera,
+            r#"{{ "attribute-test" | l10n(lang=lang, attribute="attribute_b") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("there!", html);
+    }
+
+    #[test]
+    fn can_rename_params() {
+        let loc = get_localizer().set_tera_config(
+            LocalizerTeraConfig::new()
+                .lang_param("locale")
+                .key_param("id"),
+        );
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc);
+
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(id="test-name", locale=lang, name="Bob") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("Peg \u{2068}Bob\u{2069}", html);
+    }
+
+    #[test]
+    fn can_render_nested_args() {
+        let mut loc = Localizer::new();
+        loc.add_bundle_from_sources(
+            langid!("en"),
+            &["count-test = { $name } has { $count } messages in { $key }"],
+        )
+        .unwrap();
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc.set_use_isolating(false));
+        tera.add_raw_template(
+            "test",
+            r#"{{ fluent(key="count-test", lang=lang, args=args) }}"#,
+        )
+        .unwrap();
+
+        let mut ctx = Context::new();
+        ctx.insert("lang", "en");
+        ctx.insert(
+            "args",
+            &serde_json::json!({ "name": "Bob", "count": 3, "key": "inbox" }),
+        );
+
+        let html = tera.render("test", &ctx).unwrap();
+
+        assert_eq!("Bob has 3 messages in inbox", html);
+
+        ctx.insert("args", "Bob");
+
+        assert!(tera.render("test", &ctx).is_err());
+    }
+
+    #[test]
+    fn can_render_number_options() {
+        let mut loc = Localizer::new();
+        loc.add_function("CURRENCY", |positional, _named| match positional {
+            [FluentValue::Number(n)] => FluentValue::from(format!(
+                "{} {}",
+                n.options.currency.as_deref().unwrap_or_default(),
+                n.as_string()
+            )),
+            _ => FluentValue::Error,
+        })
+        .unwrap();
+        loc.add_bundle_from_sources(
+            langid!("en"),
+            &["price = Total: { CURRENCY($amount) }\ncount = { $amount } items"],
+        )
+        .unwrap();
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc.set_use_isolating(false));
+
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="price", lang=lang, amount=5, style="currency", currency="USD", minimumFractionDigits=2) }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("Total: USD 5.00", html);
+
+        // The options only apply to the call using them
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="count", lang=lang, amount=5) }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("5 items", html);
+
+        // The options also apply to the numbers of the args object
+        tera.add_raw_template(
+            "nested",
+            r#"{{ fluent(key="price", lang=lang, args=args, currency="USD", minimumFractionDigits=2) }}"#,
+        )
+        .unwrap();
+        let mut ctx = Context::new();
+        ctx.insert("lang", "en");
+        ctx.insert("args", &serde_json::json!({ "amount": 3 }));
+
+        assert_eq!("Total: USD 3.00", tera.render("nested", &ctx).unwrap());
+    }
+
+    #[test]
+    fn number_option_names_can_be_message_variables() {
+        let mut loc = Localizer::new();
+        loc.add_bundle_from_sources(
+            langid!("en"),
+            &[
+                "order = { $amount } in { $currency }, { $style }\n    .title = { total }\ntotal = Total: { $amount } { $currency }",
+            ],
+        )
+        .unwrap();
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc.set_use_isolating(false));
+
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="order", lang=lang, amount=5, currency="USD", style="bold", minimumFractionDigits=2) }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("5.00 in USD, bold", html);
+
+        // Variables of referenced messages are also kept
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="order", attribute="title", lang=lang, amount=5, currency="USD") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("Total: 5 USD", html);
+    }
+
+    #[test]
+    fn can_render_missing_keys() {
+        let mut tera = Tera::default();
+        tera.register_function("fluent", get_localizer());
+
+        assert!(render(
+            &mut tera,
+            r#"{{ fluent(key="welcome-message", lang=lang) }}"#
+        )
+        .is_err());
+
+        let loc = get_localizer()
+            .set_tera_config(LocalizerTeraConfig::new().missing_key(MissingKey::EchoKey));
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc);
+
+        let html = render(
+            &mut tera,
+            r#"<h1>{{ fluent(key="welcome-message", lang=lang) }}</h1><p>{{ fluent(key="test-key-a", lang=lang) }}</p>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "<h1>[missing: welcome-message]</h1><p>Hello World</p>",
+            html
+        );
+
+        let loc = get_localizer()
+            .set_tera_config(LocalizerTeraConfig::new().missing_key(MissingKey::Empty));
+        let mut tera = Tera::default();
+        tera.register_filter("l10n", loc);
+
+        let html = render(&mut tera, r#"{{ "welcome-message" | l10n(lang=lang) }}"#).unwrap();
+
+        assert_eq!("", html);
+    }
+
+    #[test]
+    fn can_render_bound_locale() {
+        let loc = Arc::new(get_localizer());
+        let mut tera = Tera::default();
+        tera.register_function("fluent", loc.for_locale(langid!("en")));
+        tera.register_filter("l10n", loc.for_locale(langid!("en")));
+
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="test-key-a") }} {{ key | l10n(name="Bob") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("Hello World Peg \u{2068}Bob\u{2069}", html);
+    }
+
+    #[test]
+    fn can_render_shared_localizer() {
+        let loc = Arc::new(get_localizer());
+        let mut tera = Tera::default();
+        tera.register_function("fluent", SharedLocalizer::from(loc.clone()));
+        tera.register_filter("l10n", SharedLocalizer::from(loc.clone()));
+
+        let html = render(
+            &mut tera,
+            r#"{{ fluent(key="test-key-a", lang=lang) }} {{ key | l10n(lang=lang, name="Bob") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("Hello World Peg \u{2068}Bob\u{2069}", html);
+        assert_eq!(Arc::strong_count(&loc), 3);
+        assert!(loc.get_locale(&langid!("en")).is_some());
+    }
+
+    #[test]
+    fn language_identifiers_round_trip_through_serde() {
+        // The `lang` context value can be stored and restored as JSON, ex. in a session
+        let lang: LanguageIdentifier = serde_json::from_str(r#""ja""#).unwrap();
+
+        assert_eq!(lang, langid!("ja"));
+        assert_eq!(serde_json::to_string(&lang).unwrap(), r#""ja""#);
+        assert!(serde_json::from_str::<LanguageIdentifier>(r#""not-a-locale""#).is_err());
+    }
+
+    #[test]
+    fn can_render_locale_direction() {
+        let mut tera = Tera::default();
+        tera.register_function("locale_dir", LocaleDirection);
+
+        let html = render(
+            &mut tera,
+            r#"{{ locale_dir(lang="ar") }} {{ locale_dir(lang=lang) }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("rtl ltr", html);
+        assert!(render(&mut tera, r#"{{ locale_dir() }}"#).is_err());
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn can_render_datetime() {
+        let mut tera = Tera::default();
+        tera.register_function("format_datetime", FormatDatetime);
+
+        let html = render(
+            &mut tera,
+            r#"{{ format_datetime(timestamp=1709649000, lang=lang) }}|{{ format_datetime(timestamp="2024-03-05T23:30:00+09:00", lang="ja", style="datetime") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("3/5/2024|2024/03/05 23:30", html);
+
+        assert!(render(
+            &mut tera,
+            r#"{{ format_datetime(timestamp=1709649000, lang=lang, style="long") }}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn can_render_localized_path() {
+        let mut tera = Tera::default();
+        tera.register_function(
+            "localized_path",
+            LocalizedPath(RedirectMode::RedirectToLanguageSubPath),
+        );
+
+        let html = render(
+            &mut tera,
+            r#"{{ localized_path(lang="ja", path="/products/42?page=2") }}"#,
+        )
+        .unwrap();
+
+        assert_eq!("/ja/products/42?page=2", html);
+        assert!(render(&mut tera, r#"{{ localized_path(lang=lang) }}"#).is_err());
+    }
+
+    #[test]
+    fn can_render_hreflang_alternates() {
+        let layer = crate::LanguageIdentifierExtractorLayer::new(
+            langid!("en"),
+            vec![langid!("en"), langid!("ja")],
+            RedirectMode::RedirectToLanguageSubPath,
+        );
+        let mut tera = Tera::default();
+        tera.register_function("alternates", layer.hreflang_alternates());
 
-        let fluent_num = json_value_to_fluent_value(&num, &FluentNumberOptions::default());
+        let html = render(
+            &mut tera,
+            r#"{% for alternate in alternates(path="/about") %}<link hreflang="{{ alternate.hreflang }}" href="{{ alternate.href }}">{% endfor %}"#,
+        )
+        .unwrap();
 
         assert_eq!(
-            fluent_num,
-            FluentValue::from(FluentNumber::new(2_f64, FluentNumberOptions::default()))
+            r#"<link hreflang="en" href="/en/about"><link hreflang="ja" href="/ja/about">"#,
+            html
         );
     }
 }
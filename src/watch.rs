@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, RwLockReadGuard},
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use unic_langid::LanguageIdentifier;
+
+use crate::{fluent::LocalizerError, Localizer};
+
+type WatchedPaths = HashMap<LanguageIdentifier, Vec<PathBuf>>;
+
+/// A [Localizer] that re-parses its bundles when their FTL files change on disk.
+///
+/// Intended for use during development. Bundles are swapped behind a `RwLock`, so messages
+/// can still be formatted from other threads while a reload is happening.
+///
+/// # Example
+/// ```ignore
+/// let localizer = axum_l10n::WatchedLocalizer::new(Localizer::new(), |err| eprintln!("{err}"))?;
+/// localizer.add_bundle(ENGLISH, &["locales/en/main.ftl"])?;
+///
+/// let message = localizer.read().format_message(&ENGLISH, "test-key-a", None);
+/// ```
+pub struct WatchedLocalizer {
+    localizer: Arc<RwLock<Localizer>>,
+    paths: Arc<RwLock<WatchedPaths>>,
+    watcher: RecommendedWatcher,
+}
+
+impl std::fmt::Debug for WatchedLocalizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Watched{:?}", self.read())
+    }
+}
+
+impl WatchedLocalizer {
+    /// Wraps a localizer and starts the file watcher.
+    ///
+    /// Only bundles added through [WatchedLocalizer::add_bundle] are watched. Failed reloads
+    /// and watcher errors are passed to `on_error`, and emitted as warnings with the `tracing`
+    /// feature.
+    pub fn new<F>(localizer: Localizer, on_error: F) -> Result<Self, LocalizerError>
+    where
+        F: Fn(LocalizerError) + Send + 'static,
+    {
+        let localizer = Arc::new(RwLock::new(localizer));
+        let paths: Arc<RwLock<WatchedPaths>> = Arc::new(RwLock::new(HashMap::new()));
+
+        let watcher = {
+            let localizer = localizer.clone();
+            let paths = paths.clone();
+
+            notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+                Ok(event) if event.kind.is_modify() || event.kind.is_create() => {
+                    reload_changed(&localizer, &paths, &event.paths, &on_error)
+                }
+                Ok(_) => {}
+                Err(err) => report_error(
+                    LocalizerError::Watch {
+                        path: None,
+                        source: err,
+                    },
+                    &on_error,
+                ),
+            })
+            .map_err(|err| LocalizerError::Watch {
+                path: None,
+                source: err,
+            })?
+        };
+
+        Ok(Self {
+            localizer,
+            paths,
+            watcher,
+        })
+    }
+
+    /// Adds a bundle like [Localizer::add_bundle] and watches its FTL files for changes.
+    pub fn add_bundle<P>(
+        &mut self,
+        locale: LanguageIdentifier,
+        ftl_paths: &[P],
+    ) -> Result<(), LocalizerError>
+    where
+        P: Debug + AsRef<Path>,
+    {
+        let bundle = self.read().load_bundle(&locale, ftl_paths)?;
+
+        let mut watched = Vec::with_capacity(ftl_paths.len());
+        for path in ftl_paths {
+            let path = path
+                .as_ref()
+                .canonicalize()
+                .map_err(|err| LocalizerError::Io {
+                    path: path.as_ref().to_owned(),
+                    source: err,
+                })?;
+
+            // Watch the parent directory, as many editors replace files on save
+            let dir = path.parent().unwrap_or(&path);
+            self.watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(|err| LocalizerError::Watch {
+                    path: Some(path.clone()),
+                    source: err,
+                })?;
+
+            watched.push(path);
+        }
+
+        self.localizer
+            .write()
+            .expect("localizer lock poisoned")
+            .insert_bundle(locale.clone(), bundle);
+        self.paths
+            .write()
+            .expect("watched paths lock poisoned")
+            .insert(locale, watched);
+
+        Ok(())
+    }
+
+    /// Locks the localizer for reading, blocking only while a bundle is being swapped.
+    pub fn read(&self) -> RwLockReadGuard<'_, Localizer> {
+        self.localizer.read().expect("localizer lock poisoned")
+    }
+
+    /// Returns the shared localizer, which will keep being updated while this watcher lives.
+    pub fn localizer(&self) -> Arc<RwLock<Localizer>> {
+        self.localizer.clone()
+    }
+}
+
+/// Re-parses every bundle containing one of the changed files.
+///
+/// Bundles that fail to parse are left as they were and the error is reported.
+fn reload_changed(
+    localizer: &RwLock<Localizer>,
+    paths: &RwLock<WatchedPaths>,
+    changed: &[PathBuf],
+    on_error: &impl Fn(LocalizerError),
+) {
+    let paths = paths.read().expect("watched paths lock poisoned");
+
+    for (locale, ftl_paths) in paths.iter() {
+        let affected = ftl_paths.iter().any(|path| {
+            changed
+                .iter()
+                .any(|changed| changed.canonicalize().is_ok_and(|changed| &changed == path))
+        });
+        if !affected {
+            continue;
+        }
+
+        // Parse while formatting can continue, then hold the write lock only for the swap
+        let bundle = localizer
+            .read()
+            .expect("localizer lock poisoned")
+            .load_bundle(locale, ftl_paths);
+
+        match bundle {
+            Ok(bundle) => localizer
+                .write()
+                .expect("localizer lock poisoned")
+                .insert_bundle(locale.clone(), bundle),
+            Err(err) => report_error(err, on_error),
+        }
+    }
+}
+
+/// Emits the error as a warning with the `tracing` feature, then passes it to `on_error`
+fn report_error(err: LocalizerError, on_error: &impl Fn(LocalizerError)) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(error = %err, "failed to reload watched FTL files");
+
+    on_error(err);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use unic_langid::langid;
+
+    use super::*;
+
+    const ENGLISH: LanguageIdentifier = langid!("en");
+
+    #[test]
+    fn reloads_changed_bundle() {
+        let dir = std::env::temp_dir().join(format!("axum_l10n_watch_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ftl_path = dir.join("main.ftl");
+        std::fs::write(&ftl_path, "test-key-a = Hello World").unwrap();
+
+        let mut localizer = WatchedLocalizer::new(Localizer::new(), |_| {}).unwrap();
+        localizer.add_bundle(ENGLISH, &[&ftl_path]).unwrap();
+
+        assert_eq!(
+            Some(String::from("Hello World")),
+            localizer
+                .read()
+                .format_message(&ENGLISH, "test-key-a", None)
+        );
+
+        std::fs::write(&ftl_path, "test-key-a = Hello Again").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut message = None;
+        while Instant::now() < deadline {
+            message = localizer
+                .read()
+                .format_message(&ENGLISH, "test-key-a", None);
+            if message.as_deref() == Some("Hello Again") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(Some(String::from("Hello Again")), message);
+    }
+
+    #[test]
+    fn reports_failed_reloads() {
+        let dir =
+            std::env::temp_dir().join(format!("axum_l10n_watch_error_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ftl_path = dir.join("main.ftl");
+        std::fs::write(&ftl_path, "test-key-a = Hello World").unwrap();
+
+        let (sender, errors) = std::sync::mpsc::channel();
+        let mut localizer = WatchedLocalizer::new(Localizer::new(), move |err| {
+            let _ = sender.send(err.to_string());
+        })
+        .unwrap();
+        localizer.add_bundle(ENGLISH, &[&ftl_path]).unwrap();
+
+        // Replace the file at once, as a truncated file would be reloaded as an empty bundle
+        let tmp_path = dir.join("main.ftl.tmp");
+        std::fs::write(&tmp_path, "test-key-a Hello Again").unwrap();
+        std::fs::rename(&tmp_path, &ftl_path).unwrap();
+        let error = errors.recv_timeout(Duration::from_secs(10));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.is_ok());
+        // The previous bundle is kept
+        assert_eq!(
+            Some(String::from("Hello World")),
+            localizer
+                .read()
+                .format_message(&ENGLISH, "test-key-a", None)
+        );
+    }
+}
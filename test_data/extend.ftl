@@ -0,0 +1,2 @@
+test-key-a = Hello Plugin
+plugin-key = Plugin message
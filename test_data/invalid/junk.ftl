@@ -0,0 +1,3 @@
+valid-a = Valid message
+this is not valid
+valid-b = Another valid message
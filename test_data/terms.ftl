@@ -0,0 +1,2 @@
+-brand-name = Axum L10n
+welcome = Welcome to { -brand-name }
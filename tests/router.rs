@@ -0,0 +1,133 @@
+//! Sends requests through the layer wrapping a real axum `Router`
+
+use axum::{body::Body, http::Request, routing::get, Extension, Router};
+use axum_l10n::{LanguageIdentifierExtractorLayer, Locale, LocaleSource, RedirectMode};
+use http::StatusCode;
+use tower::{Layer, ServiceExt};
+use unic_langid::{langid, LanguageIdentifier};
+
+const ENGLISH: LanguageIdentifier = langid!("en");
+const JAPANESE: LanguageIdentifier = langid!("ja");
+
+async fn locale_handler(Locale(lang): Locale, source: LocaleSource) -> String {
+    format!("{lang} {source:?}")
+}
+
+async fn health_handler(lang: Option<Extension<LanguageIdentifier>>) -> String {
+    match lang {
+        Some(Extension(lang)) => format!("ok {lang}"),
+        None => String::from("ok"),
+    }
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/", get(locale_handler))
+        .route("/lists", get(locale_handler))
+        .route("/api/health", get(health_handler))
+}
+
+async fn body_string(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn resolves_locale_without_redirect() {
+    let app = router().layer(
+        LanguageIdentifierExtractorLayer::new(
+            ENGLISH,
+            vec![ENGLISH, JAPANESE],
+            RedirectMode::NoRedirect,
+        )
+        .set_content_language(true),
+    );
+
+    let req = Request::builder()
+        .uri("/lists")
+        .header("Accept-Language", "ja-JP,en;q=0.8")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    // The supported language matching the requested locale is served
+    assert_eq!(response.headers().get("Content-Language").unwrap(), "ja");
+    assert_eq!(body_string(response).await, "ja Header");
+
+    let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(body_string(response).await, "en Default");
+}
+
+#[tokio::test]
+async fn redirects_and_routes_localized_paths() {
+    // The layer wraps the router, so that the rewritten path is routed
+    let app = LanguageIdentifierExtractorLayer::new(
+        ENGLISH,
+        vec![ENGLISH, JAPANESE],
+        RedirectMode::RedirectToLanguageSubPath,
+    )
+    .layer(router());
+
+    let req = Request::builder()
+        .uri("/lists?page=2")
+        .header("Accept-Language", "ja")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(
+        response.headers().get("Location").unwrap(),
+        "/ja/lists?page=2"
+    );
+
+    let req = Request::builder()
+        .uri("/ja/lists?page=2")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_string(response).await, "ja Uri");
+
+    let req = Request::builder().uri("/ja").body(Body::empty()).unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(body_string(response).await, "ja Uri");
+}
+
+#[tokio::test]
+async fn excluded_paths_reach_router_unlocalized() {
+    let app = LanguageIdentifierExtractorLayer::new(
+        ENGLISH,
+        vec![ENGLISH, JAPANESE],
+        RedirectMode::RedirectToLanguageSubPath,
+    )
+    .excluded_paths(&["/api"])
+    .layer(router());
+
+    let req = Request::builder()
+        .uri("/api/health")
+        .header("Accept-Language", "ja")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_string(response).await, "ok");
+
+    // Paths that are not routed still get the router's fallback
+    let req = Request::builder()
+        .uri("/en/missing")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}